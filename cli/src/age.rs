@@ -0,0 +1,155 @@
+use std::{
+    fmt,
+    str::FromStr,
+    time::Duration,
+};
+
+static MINUTE: u64 = 60;
+static HOUR: u64 = 60 * MINUTE;
+static DAY: u64 = 24 * HOUR;
+static WEEK: u64 = 7 * DAY;
+static MONTH: u64 = 30 * DAY;
+static YEAR: u64 = 365 * DAY;
+
+/// A human-friendly duration, eg `90s`, `1h`, `180d`, `2w`, `1y`,
+/// used for age thresholds
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Age(pub Duration);
+
+impl Age {
+    pub fn as_duration(self) -> Duration {
+        self.0
+    }
+    /// Format with the two biggest units, eg `6d 4h`, for
+    /// durations which are read rather than typed
+    pub fn precise(self) -> String {
+        let secs = self.0.as_secs();
+        let units = [(YEAR, "y"), (DAY, "d"), (HOUR, "h"), (MINUTE, "m")];
+        let Some(i) = units.iter().position(|&(unit, _)| secs >= unit) else {
+            return self.to_string();
+        };
+        let (unit, name) = units[i];
+        let mut s = format!("{}{}", secs / unit, name);
+        if let Some(&(small, small_name)) = units.get(i + 1) {
+            let rest = (secs % unit) / small;
+            if rest > 0 {
+                s.push_str(&format!(" {}{}", rest, small_name));
+            }
+        }
+        s
+    }
+}
+
+impl FromStr for Age {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, String> {
+        let s = s.trim();
+        let cut = s
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(s.len());
+        let (digits, unit) = s.split_at(cut);
+        let n: u64 = digits.parse().map_err(|_| {
+            format!("Invalid age {:?}, expected <number><s|m|h|d|w|mo|y>, eg `180d`", s)
+        })?;
+        let factor = match unit {
+            "s" => 1,
+            "m" | "min" => MINUTE,
+            "h" => HOUR,
+            "" | "d" => DAY,
+            "w" => WEEK,
+            "mo" => MONTH,
+            "y" => YEAR,
+            _ => {
+                return Err(format!("Invalid age unit {:?} in {:?}", unit, s));
+            }
+        };
+        let secs = n
+            .checked_mul(factor)
+            .ok_or_else(|| format!("Age {:?} is too big", s))?;
+        Ok(Self(Duration::from_secs(secs)))
+    }
+}
+
+impl fmt::Display for Age {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let secs = self.0.as_secs();
+        let (n, unit) = if secs >= YEAR {
+            (secs / YEAR, "y")
+        } else if secs >= MONTH {
+            (secs / MONTH, "mo")
+        } else if secs >= WEEK {
+            (secs / WEEK, "w")
+        } else if secs >= DAY {
+            (secs / DAY, "d")
+        } else if secs >= HOUR {
+            (secs / HOUR, "h")
+        } else if secs >= MINUTE {
+            (secs / MINUTE, "m")
+        } else {
+            (secs, "s")
+        };
+        write!(f, "{}{}", n, unit)
+    }
+}
+
+/// The age classes used in age reports
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AgeBucket {
+    Day,
+    Week,
+    Month,
+    Year,
+    Older,
+}
+
+impl AgeBucket {
+    pub const ALL: [Self; 5] = [Self::Day, Self::Week, Self::Month, Self::Year, Self::Older];
+    pub fn of(age: Duration) -> Self {
+        let secs = age.as_secs();
+        if secs < DAY {
+            Self::Day
+        } else if secs < WEEK {
+            Self::Week
+        } else if secs < MONTH {
+            Self::Month
+        } else if secs < YEAR {
+            Self::Year
+        } else {
+            Self::Older
+        }
+    }
+    pub fn index(self) -> usize {
+        self as usize
+    }
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Day => "< 1 day",
+            Self::Week => "< 1 week",
+            Self::Month => "< 1 month",
+            Self::Year => "< 1 year",
+            Self::Older => "older",
+        }
+    }
+}
+
+#[test]
+fn test_parse_age() {
+    assert_eq!("180d".parse::<Age>().unwrap().0, Duration::from_secs(180 * DAY));
+    assert_eq!("1h".parse::<Age>().unwrap().0, Duration::from_secs(HOUR));
+    assert_eq!("30".parse::<Age>().unwrap().0, Duration::from_secs(30 * DAY));
+    assert_eq!("2y".parse::<Age>().unwrap().0, Duration::from_secs(2 * YEAR));
+    assert!("1x".parse::<Age>().is_err());
+    assert!("d".parse::<Age>().is_err());
+    assert!("99999999999999y".parse::<Age>().is_err());
+}
+
+#[test]
+fn test_age_buckets() {
+    assert_eq!(AgeBucket::of(Duration::from_secs(5)), AgeBucket::Day);
+    assert_eq!(AgeBucket::of(Duration::from_secs(3 * DAY)), AgeBucket::Week);
+    assert_eq!(AgeBucket::of(Duration::from_secs(400 * DAY)), AgeBucket::Older);
+    assert_eq!(Age(Duration::from_secs(3 * HOUR + 5)).to_string(), "3h");
+    assert_eq!(Age(Duration::from_secs(6 * DAY + 4 * HOUR + 5)).precise(), "6d 4h");
+    assert_eq!(Age(Duration::from_secs(2 * DAY + 30)).precise(), "2d");
+    assert_eq!(Age(Duration::from_secs(42)).precise(), "42s");
+}
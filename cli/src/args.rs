@@ -1,13 +1,18 @@
 use {
     crate::{
+        age::Age,
+        col_expr::parse_integer,
         cols::Cols,
         filter::Filter,
+        group::GroupBy,
+        limit::Limit,
         sorting::Sorting,
         timeout::Timeout,
         units::Units,
     },
     clap::{
         Parser,
+        Subcommand,
         ValueEnum,
     },
     termimad::crossterm::tty::IsTty,
@@ -44,11 +49,11 @@ pub struct Args {
     pub all: bool,
 
     /// whether to have styles and colors
-    #[arg(long, default_value = "auto", value_name = "color")]
+    #[arg(long, global = true, default_value = "auto", value_name = "color")]
     pub color: TriBool,
 
     /// use only ASCII characters for table rendering
-    #[arg(long)]
+    #[arg(long, global = true)]
     pub ascii: bool,
 
     /// fetch stats of remote volumes
@@ -72,16 +77,20 @@ pub struct Args {
     #[arg(short, long, value_name = "expr")]
     pub filter: Option<Filter>,
 
+    /// group the mounts, with subtotals and a total, eg `--group-by disk` (disk view, table and JSON)
+    #[arg(long, value_name = "key")]
+    pub group_by: Option<GroupBy>,
+
     /// sort, eg `inodes`, `type-desc`, or `size-asc`
     #[arg(short, long, default_value = "size", value_name = "sort")]
     pub sort: Sorting,
 
     /// units: `SI` (SI norm), `binary` (1024 based), or `bytes` (raw number)
-    #[arg(short, long, default_value = "SI", value_name = "unit")]
+    #[arg(short, long, global = true, default_value = "SI", value_name = "unit")]
     pub units: Units,
 
     /// output as JSON
-    #[arg(short, long)]
+    #[arg(short, long, global = true)]
     pub json: bool,
 
     /// output as CSV
@@ -108,13 +117,53 @@ pub struct Args {
     #[arg(long)]
     pub size_on_disk: bool,
 
+    /// show the block devices as a tree, from the disks to the mounted filesystems
+    #[arg(long)]
+    pub devices: bool,
+
+    /// show the filesystems as seen by this process, which may be in another mount namespace
+    /// (disk view)
+    #[arg(long, value_name = "pid")]
+    pub pid: Option<u32>,
+
+    /// show the filesystems as seen by this docker, podman or containerd container, given by
+    /// id or name (disk view)
+    #[arg(long, value_name = "id", conflicts_with = "pid")]
+    pub container: Option<String>,
+
+    /// read the mounts from this copy of a `/proc/self/mountinfo` file instead of
+    /// the running system (disk view)
+    #[arg(long, value_name = "file", conflicts_with_all = ["pid", "container", "record"])]
+    pub mountinfo_file: Option<PathBuf>,
+
+    /// with `--mountinfo-file`, take the stats, disks and labels from this output
+    /// of `provis -a -j` captured on the same system
+    #[arg(long, value_name = "file", requires = "mountinfo_file")]
+    pub stats_file: Option<PathBuf>,
+
+    /// show the subvolumes and snapshots of the btrfs filesystems, and the datasets of the ZFS pools
+    #[arg(long)]
+    pub volumes: bool,
+
+    /// append a snapshot of the usage of the mounts to the history file, eg from a cron job
+    #[arg(long)]
+    pub record: bool,
+
+    /// period over which the `eta_full` and `growth` columns are computed, eg `7d`
+    #[arg(long, global = true, default_value = "30d", value_name = "age")]
+    pub history_window: Age,
+
     /// scan from root directory
     #[arg(long)]
     pub root: bool,
 
-    /// number of results to show (10, 20, or all)
-    #[arg(long, default_value = "20")]
-    pub limit: String,
+    /// number of results to show, a positive number or `all`
+    #[arg(long, global = true, default_value = "20", value_name = "limit")]
+    pub limit: Limit,
+
+    /// hide the directories and files smaller than this size, eg `500M` (size-on-disk view)
+    #[arg(long, value_name = "size", value_parser = parse_integer)]
+    pub min_size: Option<u64>,
 
     /// subdirectory depth (default: 1 for immediate children)
     #[arg(long, default_value = "1")]
@@ -124,9 +173,104 @@ pub struct Args {
     #[arg(long)]
     pub recursive: bool,
 
+    /// show the largest files (size-on-disk view), `--files=both` to also show directories
+    #[arg(
+        long,
+        value_name = "mode",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "only"
+    )]
+    pub files: Option<FilesMode>,
+
+    /// show the scanned space by file type and extension (size-on-disk view)
+    #[arg(long)]
+    pub types: bool,
+
+    /// show the scanned space by modification and access age (size-on-disk view)
+    #[arg(long)]
+    pub ages: bool,
+
+    /// show the scanned space by owning user and group (size-on-disk view)
+    #[arg(long)]
+    pub by_owner: bool,
+
+    /// with `--by-owner`, also show this number of top directories, at `--depth`, for each user
+    #[arg(long, value_name = "count")]
+    pub owner_dirs: Option<usize>,
+
+    /// find duplicate files, the most wasteful first (size-on-disk view)
+    #[arg(long)]
+    pub duplicates: bool,
+
+    /// show the scanned directories as a tree, down to `--depth` (size-on-disk view)
+    #[arg(long)]
+    pub tree: bool,
+
+    /// find the caches and build artifacts, like `node_modules` or `~/.cargo/registry`,
+    /// and sum the space they take by category (size-on-disk view)
+    #[arg(long)]
+    pub reclaimable: bool,
+
+    /// browse the scanned directories interactively (size-on-disk view)
+    #[arg(long)]
+    pub interactive: bool,
+
+    /// keep an index of the scanned directories in `$XDG_CACHE_HOME/provis` and only
+    /// re-read the directories which changed since the previous scan (size-on-disk view)
+    #[arg(long)]
+    pub cache: bool,
+
+    /// exit with an error when some files or directories couldn't be read (size-on-disk view)
+    #[arg(long)]
+    pub strict: bool,
+
+    /// whether to show the progress of directory scans on stderr
+    #[arg(long, default_value = "auto", value_name = "mode")]
+    pub progress: ProgressMode,
+
+    /// write the whole scanned tree, in ncdu's JSON dump format, implies `--recursive`
+    /// (size-on-disk view)
+    #[arg(long, value_name = "file")]
+    pub export_ncdu: Option<PathBuf>,
+
+    /// analyse a dump written by `ncdu -o` (or `--export-ncdu`) instead of scanning
+    /// (size-on-disk view)
+    #[arg(long, value_name = "file")]
+    pub import_ncdu: Option<PathBuf>,
+
+    /// in the tree, collapse the directories weighing less than this percentage of their parent
+    #[arg(long, default_value = "1", value_name = "percent")]
+    pub min_percent: f32,
+
+    /// only count files not modified for this long, eg `180d` (size-on-disk view)
+    #[arg(long, value_name = "age")]
+    pub older_than: Option<Age>,
+
+    /// only count files modified within this duration, eg `1h` (size-on-disk view)
+    #[arg(long, value_name = "age")]
+    pub newer_than: Option<Age>,
+
     /// if provided, only the device holding this path will be shown (disk view)
     /// or the starting path for directory scan (size-on-disk view)
     pub path: Option<PathBuf>,
+
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// chart the recorded usage of a mount
+    History {
+        /// the mount point, or any path on the filesystem
+        mount: PathBuf,
+    },
+    /// compare two JSON outputs of provis, eg taken before and after a deployment
+    Diff {
+        before: PathBuf,
+        after: PathBuf,
+    },
 }
 
 /// This is an Option<bool> but I didn't find any way to configure
@@ -153,6 +297,24 @@ impl TriBool {
     }
 }
 
+/// Which lists the size-on-disk view shows when `--files` is given
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FilesMode {
+    /// only the largest files
+    Only,
+    /// the largest directories then the largest files
+    Both,
+}
+
+/// When to display the progress line of directory scans
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProgressMode {
+    /// only when stderr is a terminal
+    Auto,
+    Always,
+    Never,
+}
+
 impl Args {
     pub fn color(&self) -> bool {
         self.color.unwrap_or_else(|| std::io::stdout().is_tty())
@@ -1,6 +1,12 @@
 use {
-    crate::order::Order,
-    lfs_core::Mount,
+    crate::{
+        diskio::{self, DiskIo},
+        hardware::{self, DiskHardware},
+        history::{self, Trends},
+        order::Order,
+        reserved,
+    },
+    lfs_core::{Mount, Stats},
     std::{
         cmp::Ordering,
         fmt,
@@ -96,6 +102,9 @@ col_enum!(
     UsePercent "use_percent": "use%",
     Free "free": "free" default,
     FreePercent "free_percent": "free%",
+    Reserved "reserved" "rsv": "reserved",
+    ReservedPercent "reserved_percent" "rsv_percent": "rsv%",
+    FreeRoot "free_root" "root_free": "free root",
     Size "size": "size" default,
     InodesUsed "inodes_used" "iused": "used inodes",
     InodesUse "inodes" "ino" "inodes_use" "iuse": "inodes",
@@ -107,6 +116,21 @@ col_enum!(
     PartUuid "partuuid" "part_uuid": "PARTUUID",
     MountOptions "options" "mount_options": "mount options",
     CompressLevel "compress" "compress_level": "compress",
+    Model "model" "vendor": "model",
+    Serial "serial": "serial",
+    Transport "transport" "tran": "transport",
+    Scheduler "scheduler" "sched": "scheduler",
+    LogicalSector "logical_sector" "log_sec": "log sec",
+    PhysicalSector "physical_sector" "phy_sec": "phy sec",
+    Discard "discard" "trim": "discard",
+    ReadAhead "read_ahead" "ra": "read-ahead",
+    ReadRate "read_s" "reads": "read/s",
+    WriteRate "write_s" "writes": "write/s",
+    Iops "iops": "IOPS",
+    Latency "latency" "await": "latency",
+    Util "util" "busy": "util%",
+    EtaFull "eta_full" "eta": "full in",
+    Growth "growth" "growth_day": "growth/day",
 );
 
 impl Col {
@@ -115,6 +139,8 @@ impl Col {
             Self::Label => Alignment::Left,
             Self::MountPoint => Alignment::Left,
             Self::MountOptions => Alignment::Left,
+            Self::Model => Alignment::Left,
+            Self::Serial => Alignment::Left,
             _ => Alignment::Center,
         }
     }
@@ -132,6 +158,9 @@ impl Col {
             Self::UsePercent => Alignment::Right,
             Self::Free => Alignment::Right,
             Self::FreePercent => Alignment::Right,
+            Self::Reserved => Alignment::Right,
+            Self::ReservedPercent => Alignment::Right,
+            Self::FreeRoot => Alignment::Right,
             Self::Size => Alignment::Right,
             Self::InodesUsed => Alignment::Right,
             Self::InodesUse => Alignment::Right,
@@ -143,6 +172,21 @@ impl Col {
             Self::PartUuid => Alignment::Left,
             Self::MountOptions => Alignment::Left,
             Self::CompressLevel => Alignment::Center,
+            Self::Model => Alignment::Left,
+            Self::Serial => Alignment::Left,
+            Self::Transport => Alignment::Center,
+            Self::Scheduler => Alignment::Center,
+            Self::LogicalSector => Alignment::Right,
+            Self::PhysicalSector => Alignment::Right,
+            Self::Discard => Alignment::Center,
+            Self::ReadAhead => Alignment::Right,
+            Self::ReadRate => Alignment::Right,
+            Self::WriteRate => Alignment::Right,
+            Self::Iops => Alignment::Right,
+            Self::Latency => Alignment::Right,
+            Self::Util => Alignment::Right,
+            Self::EtaFull => Alignment::Right,
+            Self::Growth => Alignment::Right,
         }
     }
     pub fn description(self) -> &'static str {
@@ -159,6 +203,9 @@ impl Col {
             Self::UsePercent => "percentage of blocks used",
             Self::Free => "free bytes",
             Self::FreePercent => "percentage of free blocks",
+            Self::Reserved => "space reserved for root, free but not available",
+            Self::ReservedPercent => "percentage of blocks reserved for root",
+            Self::FreeRoot => "free bytes for root, reserved space included",
             Self::Size => "total size",
             Self::InodesUsed => "number of inodes used",
             Self::InodesUse => "graphical view of inodes usage",
@@ -170,6 +217,21 @@ impl Col {
             Self::PartUuid => "partition UUID",
             Self::MountOptions => "mount options (linux only)",
             Self::CompressLevel => "compress algo/level",
+            Self::Model => "vendor and model of the disk",
+            Self::Serial => "serial number of the disk",
+            Self::Transport => "bus of the disk: nvme, sata, usb, virtio...",
+            Self::Scheduler => "I/O scheduler of the disk",
+            Self::LogicalSector => "logical sector size, in bytes",
+            Self::PhysicalSector => "physical sector size, in bytes",
+            Self::Discard => "whether the disk supports discard (TRIM)",
+            Self::ReadAhead => "read-ahead of the disk",
+            Self::ReadRate => "bytes read per second on the disk",
+            Self::WriteRate => "bytes written per second on the disk",
+            Self::Iops => "read and write requests per second on the disk",
+            Self::Latency => "average time of a request on the disk, in ms",
+            Self::Util => "percentage of time the disk was busy",
+            Self::EtaFull => "time until full, at the recorded growth rate",
+            Self::Growth => "growth of the used space per day, from the recorded history",
         }
     }
     pub fn comparator(self) -> impl for<'a, 'b> FnMut(&'a Mount, &'b Mount) -> Ordering {
@@ -221,6 +283,9 @@ impl Col {
                 (None, Some(_)) => Ordering::Less,
                 (None, None) => Ordering::Equal,
             },
+            Self::Reserved => |a: &Mount, b: &Mount| cmp_stats(a, b, reserved::reserved),
+            Self::ReservedPercent => |a: &Mount, b: &Mount| cmp_stats(a, b, reserved::reserved_share),
+            Self::FreeRoot => |a: &Mount, b: &Mount| cmp_stats(a, b, reserved::free_for_root),
             Self::Size => |a: &Mount, b: &Mount| match (&a.stats(), &b.stats()) {
                 (Some(a), Some(b)) => a.size().cmp(&b.size()),
                 (Some(_), None) => Ordering::Greater,
@@ -275,6 +340,21 @@ impl Col {
                 (None, Some(_)) => Ordering::Greater,
                 (None, None) => Ordering::Equal,
             },
+            Self::Model => |a: &Mount, b: &Mount| cmp_hardware(a, b, DiskHardware::vendor_model),
+            Self::Serial => |a: &Mount, b: &Mount| cmp_hardware(a, b, |h| h.serial.clone()),
+            Self::Transport => |a: &Mount, b: &Mount| cmp_hardware(a, b, |h| h.transport),
+            Self::Scheduler => |a: &Mount, b: &Mount| cmp_hardware(a, b, |h| h.scheduler.clone()),
+            Self::LogicalSector => |a: &Mount, b: &Mount| cmp_hardware(a, b, |h| h.logical_sector_size),
+            Self::PhysicalSector => |a: &Mount, b: &Mount| cmp_hardware(a, b, |h| h.physical_sector_size),
+            Self::Discard => |a: &Mount, b: &Mount| cmp_hardware(a, b, |h| h.discard),
+            Self::ReadAhead => |a: &Mount, b: &Mount| cmp_hardware(a, b, |h| h.read_ahead),
+            Self::ReadRate => |a: &Mount, b: &Mount| cmp_io(a, b, |io| Some(io.read_rate)),
+            Self::WriteRate => |a: &Mount, b: &Mount| cmp_io(a, b, |io| Some(io.write_rate)),
+            Self::Iops => |a: &Mount, b: &Mount| cmp_io(a, b, |io| Some(io.iops)),
+            Self::Latency => |a: &Mount, b: &Mount| cmp_io(a, b, |io| io.latency),
+            Self::Util => |a: &Mount, b: &Mount| cmp_io(a, b, |io| Some(io.util)),
+            // compared with the history, by `cmp_trends`
+            Self::EtaFull | Self::Growth => |_: &Mount, _: &Mount| Ordering::Equal,
         }
     }
     pub fn default_sort_order(self) -> Order {
@@ -291,6 +371,9 @@ impl Col {
             Self::UsePercent => Order::Asc,
             Self::Free => Order::Asc,
             Self::FreePercent => Order::Desc,
+            Self::Reserved => Order::Desc,
+            Self::ReservedPercent => Order::Desc,
+            Self::FreeRoot => Order::Asc,
             Self::Size => Order::Desc,
             Self::InodesUsed => Order::Asc,
             Self::InodesUse => Order::Asc,
@@ -302,6 +385,46 @@ impl Col {
             Self::PartUuid => Order::Asc,
             Self::MountOptions => Order::Asc,
             Self::CompressLevel => Order::Asc,
+            Self::Model => Order::Asc,
+            Self::Serial => Order::Asc,
+            Self::Transport => Order::Asc,
+            Self::Scheduler => Order::Asc,
+            Self::LogicalSector => Order::Asc,
+            Self::PhysicalSector => Order::Asc,
+            Self::Discard => Order::Desc,
+            Self::ReadAhead => Order::Desc,
+            Self::ReadRate => Order::Desc,
+            Self::WriteRate => Order::Desc,
+            Self::Iops => Order::Desc,
+            Self::Latency => Order::Desc,
+            Self::Util => Order::Desc,
+            Self::EtaFull => Order::Asc,
+            Self::Growth => Order::Desc,
+        }
+    }
+    /// Whether the column needs the activity of the disks, which
+    /// takes a sample window to measure
+    pub fn is_io(self) -> bool {
+        matches!(
+            self,
+            Self::ReadRate | Self::WriteRate | Self::Iops | Self::Latency | Self::Util
+        )
+    }
+    /// Whether the column needs the recorded usage history
+    pub fn is_trend(self) -> bool {
+        matches!(self, Self::EtaFull | Self::Growth)
+    }
+    /// Compare two mounts on a trend column
+    pub fn cmp_trends(
+        self,
+        a: &Mount,
+        b: &Mount,
+        trends: &Trends,
+    ) -> Ordering {
+        match self {
+            Self::EtaFull => cmp_trend(a, b, trends, |t| t.eta_full.map(|d| d.as_secs_f64())),
+            Self::Growth => cmp_trend(a, b, trends, |t| Some(t.growth_per_day)),
+            _ => Ordering::Equal,
         }
     }
     pub fn default_sort_col() -> Self {
@@ -309,6 +432,75 @@ impl Col {
     }
 }
 
+/// Compare the mounts by a value computed from their stats, the
+/// mounts without stats being first, as for the other size columns
+fn cmp_stats<T: PartialOrd>(
+    a: &Mount,
+    b: &Mount,
+    f: impl Fn(&Stats) -> T,
+) -> Ordering {
+    match (a.stats(), b.stats()) {
+        // SAFETY: the values, even shares, are never NaN
+        (Some(a), Some(b)) => f(a).partial_cmp(&f(b)).unwrap(),
+        (Some(_), None) => Ordering::Greater,
+        (None, Some(_)) => Ordering::Less,
+        (None, None) => Ordering::Equal,
+    }
+}
+
+/// Compare the mounts by a property of their disk, the mounts
+/// without one being last
+fn cmp_hardware<T: Ord>(
+    a: &Mount,
+    b: &Mount,
+    f: impl Fn(&DiskHardware) -> Option<T>,
+) -> Ordering {
+    let a = hardware::of_mount(a).and_then(|h| f(&h));
+    let b = hardware::of_mount(b).and_then(|h| f(&h));
+    match (a, b) {
+        (Some(a), Some(b)) => a.cmp(&b),
+        (Some(_), None) => Ordering::Less,
+        (None, Some(_)) => Ordering::Greater,
+        (None, None) => Ordering::Equal,
+    }
+}
+
+/// Compare the mounts by the activity of their disk, the mounts
+/// without a measure being last
+fn cmp_io(
+    a: &Mount,
+    b: &Mount,
+    f: impl Fn(&DiskIo) -> Option<f64>,
+) -> Ordering {
+    let a = diskio::of_mount(a).and_then(|io| f(&io));
+    let b = diskio::of_mount(b).and_then(|io| f(&io));
+    match (a, b) {
+        // SAFETY: the measures are never NaN
+        (Some(a), Some(b)) => a.partial_cmp(&b).unwrap(),
+        (Some(_), None) => Ordering::Less,
+        (None, Some(_)) => Ordering::Greater,
+        (None, None) => Ordering::Equal,
+    }
+}
+
+/// Compare the mounts by their recorded trend, the mounts
+/// without one being last
+fn cmp_trend(
+    a: &Mount,
+    b: &Mount,
+    trends: &Trends,
+    f: impl Fn(&history::Trend) -> Option<f64>,
+) -> Ordering {
+    let a = trends.of_mount(a).and_then(|t| f(&t));
+    let b = trends.of_mount(b).and_then(|t| f(&t));
+    match (a, b) {
+        (Some(a), Some(b)) => a.total_cmp(&b),
+        (Some(_), None) => Ordering::Less,
+        (None, Some(_)) => Ordering::Greater,
+        (None, None) => Ordering::Equal,
+    }
+}
+
 #[derive(Debug)]
 pub struct ParseColError {
     /// the string which couldn't be parsed
@@ -1,5 +1,12 @@
 use {
-    crate::col::*,
+    crate::{
+        age::Age,
+        col::*,
+        diskio,
+        hardware,
+        history::Trends,
+        reserved,
+    },
     lfs_core::*,
     std::{
         fmt,
@@ -90,7 +97,20 @@ impl ColExpr {
     pub fn eval(
         &self,
         mount: &Mount,
+        trends: &Trends,
     ) -> Result<bool, EvalExprError> {
+        let hardware = match self.col {
+            Col::Model
+            | Col::Serial
+            | Col::Transport
+            | Col::Scheduler
+            | Col::LogicalSector
+            | Col::PhysicalSector
+            | Col::Discard
+            | Col::ReadAhead => hardware::of_mount(mount),
+            _ => None,
+        };
+        let hardware = hardware.as_deref();
         Ok(match self.col {
             Col::Id => self.operator.eval_option(
                 mount.info.id,
@@ -127,6 +147,23 @@ impl ColExpr {
                 mount.stats().as_ref().map(|s| s.available()),
                 parse_integer(&self.value)?,
             ),
+            // `reserved>5%` compares the share, `reserved>1T` the size
+            Col::Reserved if self.value.ends_with('%') => self.operator.eval_option(
+                mount.stats().map(reserved::reserved_share),
+                parse_float(&self.value)?,
+            ),
+            Col::Reserved => self.operator.eval_option(
+                mount.stats().map(reserved::reserved),
+                parse_integer(&self.value)?,
+            ),
+            Col::ReservedPercent => self.operator.eval_option(
+                mount.stats().map(reserved::reserved_share),
+                parse_float(&self.value)?,
+            ),
+            Col::FreeRoot => self.operator.eval_option(
+                mount.stats().map(reserved::free_for_root),
+                parse_integer(&self.value)?,
+            ),
             Col::Size => self.operator.eval_option(
                 mount.stats().as_ref().map(|s| s.size()),
                 parse_integer(&self.value)?,
@@ -160,6 +197,61 @@ impl ColExpr {
             Col::CompressLevel => self
                 .operator
                 .eval_option_str(mount.info.option_value("compress"), &self.value),
+            Col::Model => self
+                .operator
+                .eval_option_str(hardware.and_then(|h| h.vendor_model()).as_deref(), &self.value),
+            Col::Serial => self
+                .operator
+                .eval_option_str(hardware.and_then(|h| h.serial.as_deref()), &self.value),
+            Col::Transport => self
+                .operator
+                .eval_option_str(hardware.and_then(|h| h.transport), &self.value),
+            Col::Scheduler => self
+                .operator
+                .eval_option_str(hardware.and_then(|h| h.scheduler.as_deref()), &self.value),
+            Col::LogicalSector => self.operator.eval_option(
+                hardware.and_then(|h| h.logical_sector_size),
+                parse_integer(&self.value)?,
+            ),
+            Col::PhysicalSector => self.operator.eval_option(
+                hardware.and_then(|h| h.physical_sector_size),
+                parse_integer(&self.value)?,
+            ),
+            Col::Discard => self
+                .operator
+                .eval_option(hardware.and_then(|h| h.discard), parse_bool(&self.value)?),
+            Col::ReadAhead => self.operator.eval_option(
+                hardware.and_then(|h| h.read_ahead),
+                parse_integer(&self.value)?,
+            ),
+            Col::ReadRate => self.operator.eval_option(
+                diskio::of_mount(mount).map(|io| io.read_rate),
+                parse_integer(&self.value)? as f64,
+            ),
+            Col::WriteRate => self.operator.eval_option(
+                diskio::of_mount(mount).map(|io| io.write_rate),
+                parse_integer(&self.value)? as f64,
+            ),
+            Col::Iops => self.operator.eval_option(
+                diskio::of_mount(mount).map(|io| io.iops),
+                parse_float(&self.value)?,
+            ),
+            Col::Latency => self.operator.eval_option(
+                diskio::of_mount(mount).and_then(|io| io.latency),
+                parse_float(&self.value)?,
+            ),
+            Col::Util => self.operator.eval_option(
+                diskio::of_mount(mount).map(|io| io.util),
+                parse_float(&self.value)?,
+            ),
+            Col::EtaFull => self.operator.eval_option(
+                trends.of_mount(mount).and_then(|t| t.eta_full),
+                parse_duration(&self.value)?,
+            ),
+            Col::Growth => self.operator.eval_option(
+                trends.of_mount(mount).map(|t| t.growth_per_day),
+                parse_integer(&self.value)? as f64,
+            ),
         })
     }
 }
@@ -271,6 +363,7 @@ pub enum EvalExprError {
     NotAnId(String),
     NotADeviceId(String),
     NotABool(String),
+    NotADuration(String),
 }
 impl EvalExprError {}
 impl fmt::Display for EvalExprError {
@@ -291,11 +384,21 @@ impl fmt::Display for EvalExprError {
             Self::NotABool(s) => {
                 write!(f, "{:?} can't be evaluated as a boolean", &s)
             }
+            Self::NotADuration(s) => {
+                write!(f, "{:?} can't be evaluated as a duration", &s)
+            }
         }
     }
 }
 impl std::error::Error for EvalExprError {}
 
+fn parse_duration(input: &str) -> Result<std::time::Duration, EvalExprError> {
+    input
+        .parse::<Age>()
+        .map(Age::as_duration)
+        .map_err(|_| EvalExprError::NotADuration(input.to_string()))
+}
+
 fn parse_bool(input: &str) -> Result<bool, EvalExprError> {
     let s = input.to_lowercase();
     match s.as_ref() {
@@ -306,7 +409,7 @@ fn parse_bool(input: &str) -> Result<bool, EvalExprError> {
 }
 
 /// Parse numbers like "1234", "32G", "4kB", "54Gib", "1.2M"
-fn parse_integer(input: &str) -> Result<u64, EvalExprError> {
+pub(crate) fn parse_integer(input: &str) -> Result<u64, EvalExprError> {
     let s = input.to_lowercase();
     let s = s.trim_end_matches('b');
     let (s, binary) = match s.strip_suffix('i') {
@@ -213,6 +213,9 @@ mod cols_parsing {
                 UsePercent,
                 Free,
                 FreePercent,
+                Reserved,
+                ReservedPercent,
+                FreeRoot,
                 Size,
                 InodesUsed,
                 InodesUsePercent,
@@ -221,6 +224,23 @@ mod cols_parsing {
                 MountPoint,
                 Uuid,
                 PartUuid,
+                MountOptions,
+                CompressLevel,
+                Model,
+                Serial,
+                Transport,
+                Scheduler,
+                LogicalSector,
+                PhysicalSector,
+                Discard,
+                ReadAhead,
+                ReadRate,
+                WriteRate,
+                Iops,
+                Latency,
+                Util,
+                EtaFull,
+                Growth,
                 Label,
             ],
         );
@@ -234,6 +254,9 @@ mod cols_parsing {
                 Remote,
                 UsePercent,
                 FreePercent,
+                Reserved,
+                ReservedPercent,
+                FreeRoot,
                 InodesUsed,
                 InodesUse,
                 InodesUsePercent,
@@ -241,6 +264,23 @@ mod cols_parsing {
                 InodesCount,
                 Uuid,
                 PartUuid,
+                MountOptions,
+                CompressLevel,
+                Model,
+                Serial,
+                Transport,
+                Scheduler,
+                LogicalSector,
+                PhysicalSector,
+                Discard,
+                ReadAhead,
+                ReadRate,
+                WriteRate,
+                Iops,
+                Latency,
+                Util,
+                EtaFull,
+                Growth,
                 Use,
             ],
         );
@@ -253,6 +293,9 @@ mod cols_parsing {
                 Remote,
                 UsePercent,
                 FreePercent,
+                Reserved,
+                ReservedPercent,
+                FreeRoot,
                 InodesUsed,
                 InodesUse,
                 InodesUsePercent,
@@ -260,6 +303,23 @@ mod cols_parsing {
                 InodesCount,
                 Uuid,
                 PartUuid,
+                MountOptions,
+                CompressLevel,
+                Model,
+                Serial,
+                Transport,
+                Scheduler,
+                LogicalSector,
+                PhysicalSector,
+                Discard,
+                ReadAhead,
+                ReadRate,
+                WriteRate,
+                Iops,
+                Latency,
+                Util,
+                EtaFull,
+                Growth,
                 Filesystem,
                 Type,
                 Disk,
@@ -285,6 +345,9 @@ mod cols_parsing {
                 UsePercent,
                 Free,
                 FreePercent,
+                Reserved,
+                ReservedPercent,
+                FreeRoot,
                 Size,
                 InodesUsed,
                 InodesUse,
@@ -294,6 +357,23 @@ mod cols_parsing {
                 MountPoint,
                 Uuid,
                 PartUuid,
+                MountOptions,
+                CompressLevel,
+                Model,
+                Serial,
+                Transport,
+                Scheduler,
+                LogicalSector,
+                PhysicalSector,
+                Discard,
+                ReadAhead,
+                ReadRate,
+                WriteRate,
+                Iops,
+                Latency,
+                Util,
+                EtaFull,
+                Growth,
             ],
         );
         check(
@@ -309,6 +389,9 @@ mod cols_parsing {
                 UsePercent,
                 Free,
                 FreePercent,
+                Reserved,
+                ReservedPercent,
+                FreeRoot,
                 Size,
                 InodesUsed,
                 InodesUse,
@@ -318,6 +401,23 @@ mod cols_parsing {
                 MountPoint,
                 Uuid,
                 PartUuid,
+                MountOptions,
+                CompressLevel,
+                Model,
+                Serial,
+                Transport,
+                Scheduler,
+                LogicalSector,
+                PhysicalSector,
+                Discard,
+                ReadAhead,
+                ReadRate,
+                WriteRate,
+                Iops,
+                Latency,
+                Util,
+                EtaFull,
+                Growth,
             ],
         );
     }
@@ -359,6 +459,9 @@ mod cols_parsing {
                 Remote,
                 UsePercent,
                 FreePercent,
+                Reserved,
+                ReservedPercent,
+                FreeRoot,
                 InodesUsed,
                 InodesUse,
                 InodesUsePercent,
@@ -366,6 +469,23 @@ mod cols_parsing {
                 InodesCount,
                 Uuid,
                 PartUuid,
+                MountOptions,
+                CompressLevel,
+                Model,
+                Serial,
+                Transport,
+                Scheduler,
+                LogicalSector,
+                PhysicalSector,
+                Discard,
+                ReadAhead,
+                ReadRate,
+                WriteRate,
+                Iops,
+                Latency,
+                Util,
+                EtaFull,
+                Growth,
             ],
         );
         check(
@@ -23,12 +23,36 @@ pub fn print_commands<W: Write>(
     add_command(&mut expander, "(default)", "Disk/filesystem view", "provis");
     add_command(&mut expander, "--processes, -p", "Process monitoring view", "provis -p");
     add_command(&mut expander, "--size-on-disk", "Directory size analysis", "provis --size-on-disk");
+    add_command(&mut expander, "--devices", "Block device tree, disks to mounts", "provis --devices");
+    add_command(&mut expander, "--volumes", "Btrfs subvolumes and ZFS datasets", "provis --volumes");
+    add_command(&mut expander, "history MOUNT", "Chart the recorded usage of a mount", "provis history /var");
+    add_command(&mut expander, "diff BEFORE AFTER", "Compare two saved JSON outputs", "provis diff before.json after.json");
 
     // Directory Scanning Options
     add_section(&mut expander, "DIRECTORY SCANNING");
     add_command(&mut expander, "--recursive", "Scan all nested folders", "provis --size-on-disk --recursive");
     add_command(&mut expander, "--depth N", "Scan N levels deep (default: 1)", "provis --size-on-disk --depth 3");
-    add_command(&mut expander, "--limit N", "Show top N results (default: 20)", "provis --size-on-disk --limit 50");
+    add_command(&mut expander, "--limit N", "Show top N results, or all (default: 20)", "provis --size-on-disk --limit 50");
+    add_command(&mut expander, "--min-size SIZE", "Hide entries smaller than SIZE", "provis --size-on-disk --min-size 500M");
+    add_command(&mut expander, "--files", "Show the largest files instead", "provis --size-on-disk --files");
+    add_command(&mut expander, "--files=both", "Show largest directories and files", "provis --size-on-disk --files=both");
+    add_command(&mut expander, "--types", "Breakdown by file type and extension", "provis --size-on-disk --types");
+    add_command(&mut expander, "--ages", "Breakdown by modification/access age", "provis --size-on-disk --ages");
+    add_command(&mut expander, "--older-than AGE", "Only count files older than AGE", "provis --size-on-disk --older-than 180d");
+    add_command(&mut expander, "--newer-than AGE", "Only count files newer than AGE", "provis --size-on-disk --newer-than 1h");
+    add_command(&mut expander, "--duplicates", "Find duplicate files", "provis --size-on-disk --duplicates");
+    add_command(&mut expander, "--tree", "Indented tree down to --depth", "provis --size-on-disk --tree --depth 3");
+    add_command(&mut expander, "--min-percent P", "Collapse small tree nodes (default: 1)", "provis --size-on-disk --tree --min-percent 5");
+    add_command(&mut expander, "--interactive", "Browse, rescan and delete in a full-screen view", "provis --size-on-disk --interactive");
+    add_command(&mut expander, "--cache", "Only re-read directories changed since the last scan", "provis --size-on-disk /srv --recursive --cache");
+    add_command(&mut expander, "--export-ncdu FILE", "Write the whole scanned tree as an ncdu dump", "provis --size-on-disk /srv --export-ncdu srv.json");
+    add_command(&mut expander, "--import-ncdu FILE", "Analyse an ncdu dump instead of scanning", "provis --size-on-disk --import-ncdu srv.json --tree");
+    add_command(&mut expander, "--strict", "Exit with an error when entries couldn't be read", "provis --size-on-disk /srv --recursive --strict");
+    add_command(&mut expander, "--progress MODE", "Progress line with ETA: auto, always or never", "provis --size-on-disk / --recursive --progress never");
+    add_command(&mut expander, "--timeout T", "Skip directories not read within T, eg 5s", "provis --size-on-disk /mnt --recursive --timeout 5s");
+    add_command(&mut expander, "--by-owner", "Space by owning user and group", "provis --size-on-disk /scratch --by-owner");
+    add_command(&mut expander, "--owner-dirs N", "Top N directories of every owner", "provis --size-on-disk /scratch --by-owner --owner-dirs 5");
+    add_command(&mut expander, "--reclaimable", "Caches and build artifacts, by category", "provis --size-on-disk ~ --reclaimable");
     add_command(&mut expander, "--root", "Scan from filesystem root", "provis --size-on-disk --root");
     add_command(&mut expander, "--all, -a", "Show hidden dirs and build artifacts", "provis --size-on-disk -a");
     add_command(&mut expander, "PATH", "Start scan from path", "provis --size-on-disk /home");
@@ -41,6 +65,13 @@ pub fn print_commands<W: Write>(
     add_command(&mut expander, "--sort, -s", "Sort by column", "provis -s free-desc");
     add_command(&mut expander, "--filter, -f", "Filter filesystems", "provis -f 'size>100G'");
     add_command(&mut expander, "--units, -u", "Size units (SI/binary/bytes)", "provis -u binary");
+    add_command(&mut expander, "--group-by KEY", "Group with subtotals (type/disk/remote/device/label)", "provis --group-by disk");
+    add_command(&mut expander, "--pid PID", "Filesystems as seen by a process", "provis --pid 4242");
+    add_command(&mut expander, "--container ID", "Filesystems of a container, with their host paths", "provis --container web");
+    add_command(&mut expander, "--mountinfo-file FILE", "Read the mounts from a mountinfo copy", "provis -a --mountinfo-file mountinfo");
+    add_command(&mut expander, "--stats-file FILE", "Stats of the mountinfo copy, from provis -a -j", "provis --mountinfo-file mountinfo --stats-file stats.json");
+    add_command(&mut expander, "--record", "Append a usage snapshot to the history", "provis --record");
+    add_command(&mut expander, "--history-window AGE", "Period of the growth columns (default: 30d)", "provis -c +growth+eta --history-window 7d");
 
     // Display Options
     add_section(&mut expander, "DISPLAY");
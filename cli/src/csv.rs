@@ -1,7 +1,12 @@
 use {
     crate::{
         Args,
+        age::Age,
         col::Col,
+        diskio,
+        hardware,
+        history::Trends,
+        reserved,
     },
     lfs_core::*,
     std::{
@@ -11,7 +16,7 @@ use {
 };
 
 /// Utility to write in CSV
-struct Csv<W: Write> {
+pub(crate) struct Csv<W: Write> {
     separator: char,
     w: W,
 }
@@ -63,6 +68,7 @@ pub fn write<W: Write>(
     w: &mut W,
     mounts: &[&Mount],
     args: &Args,
+    trends: &Trends,
 ) -> std::io::Result<()> {
     let units = args.units;
     let mut csv = Csv::new(args.csv_separator, w);
@@ -71,6 +77,17 @@ pub fn write<W: Write>(
     }
     csv.end_line()?;
     for mount in mounts {
+        let hardware = hardware::of_mount(mount);
+        let io = if args.cols.cols().iter().any(|c| c.is_io()) {
+            diskio::of_mount(mount)
+        } else {
+            None
+        };
+        let trend = if args.cols.cols().iter().any(|c| c.is_trend()) {
+            trends.of_mount(mount)
+        } else {
+            None
+        };
         for col in args.cols.cols() {
             match col {
                 Col::Id => csv.cell_opt(mount.info.id),
@@ -93,6 +110,13 @@ pub fn write<W: Write>(
                         .stats()
                         .map(|s| format!("{:.0}%", 100.0 * (1.0 - s.use_share()))),
                 ),
+                Col::Reserved => csv.cell_opt(mount.stats().map(|s| units.fmt(reserved::reserved(s)))),
+                Col::ReservedPercent => csv.cell_opt(
+                    mount
+                        .stats()
+                        .map(|s| format!("{:.1}%", 100.0 * reserved::reserved_share(s))),
+                ),
+                Col::FreeRoot => csv.cell_opt(mount.stats().map(|s| units.fmt(reserved::free_for_root(s)))),
                 Col::Size => csv.cell_opt(mount.stats().map(|s| units.fmt(s.size()))),
                 Col::InodesUsed => csv.cell_opt(mount.inodes().map(|i| i.used())),
                 Col::InodesUse => csv.cell_opt(mount.inodes().map(|i| i.use_share())),
@@ -108,6 +132,21 @@ pub fn write<W: Write>(
                 Col::PartUuid => csv.cell(mount.part_uuid.as_ref().map_or("", |v| v)),
                 Col::MountOptions => csv.cell(mount.info.options_string()),
                 Col::CompressLevel => csv.cell_opt(mount.info.option_value("compress")),
+                Col::Model => csv.cell_opt(hardware.as_ref().and_then(|h| h.vendor_model())),
+                Col::Serial => csv.cell_opt(hardware.as_ref().and_then(|h| h.serial.as_ref())),
+                Col::Transport => csv.cell_opt(hardware.as_ref().and_then(|h| h.transport)),
+                Col::Scheduler => csv.cell_opt(hardware.as_ref().and_then(|h| h.scheduler.as_ref())),
+                Col::LogicalSector => csv.cell_opt(hardware.as_ref().and_then(|h| h.logical_sector_size)),
+                Col::PhysicalSector => csv.cell_opt(hardware.as_ref().and_then(|h| h.physical_sector_size)),
+                Col::Discard => csv.cell_opt(hardware.as_ref().and_then(|h| h.discard_str())),
+                Col::ReadAhead => csv.cell_opt(hardware.as_ref().and_then(|h| h.read_ahead).map(|r| units.fmt(r))),
+                Col::ReadRate => csv.cell_opt(io.map(|io| io.read_rate.round())),
+                Col::WriteRate => csv.cell_opt(io.map(|io| io.write_rate.round())),
+                Col::Iops => csv.cell_opt(io.map(|io| format!("{:.0}", io.iops))),
+                Col::Latency => csv.cell_opt(io.and_then(|io| io.latency).map(|l| format!("{:.1}", l))),
+                Col::Util => csv.cell_opt(io.map(|io| format!("{:.0}%", io.util))),
+                Col::EtaFull => csv.cell_opt(trend.and_then(|t| t.eta_full).map(|eta| Age(eta).precise())),
+                Col::Growth => csv.cell_opt(trend.map(|t| units.fmt_signed(t.growth_per_day as i64))),
             }?;
         }
         csv.end_line()?;
@@ -0,0 +1,164 @@
+use {
+    super::{BlockDevice, read_devices},
+    crate::{
+        Args,
+        directory::display::make_skin,
+        process::graph::render_bar_compact,
+        sysfs::Sysfs,
+    },
+    lfs_core::{DeviceId, Mount, ReadOptions},
+    serde_json::{Map, Value},
+    std::io::{self, Write},
+    termimad::minimad::{OwningTemplateExpander, TableBuilder, Col, Alignment},
+};
+
+/// Display the block devices as a tree, with the usage of their
+/// mounted filesystems
+pub fn display_devices<W: Write>(
+    w: &mut W,
+    args: &Args,
+) -> io::Result<()> {
+    let devices = read_devices(&Sysfs::default());
+    let mut options = ReadOptions::default();
+    if let Some(timeout) = args.timeout {
+        options = options.stats_timeout(timeout.as_duration());
+    }
+    let mounts = match lfs_core::read_mounts(&options) {
+        Ok(mounts) => mounts,
+        Err(e) => {
+            eprintln!("Error reading mounts: {}", e);
+            Vec::new()
+        }
+    };
+    if args.json {
+        let value: Vec<Value> = devices.iter().map(|d| device_to_json(d, &mounts)).collect();
+        return writeln!(w, "{}", serde_json::to_string_pretty(&value).unwrap());
+    }
+    if devices.is_empty() {
+        return writeln!(w, "no block device found in /sys/block");
+    }
+    write_devices(w, &devices, &mounts, args)
+}
+
+/// The mount of a device: when it's mounted several times, eg with
+/// bind mounts or subvolumes, the one closest to the root
+fn mount_of(dev: Option<DeviceId>, mounts: &[Mount]) -> Option<&Mount> {
+    let dev = dev?;
+    mounts
+        .iter()
+        .filter(|m| m.info.dev == dev && !m.info.bound)
+        .min_by_key(|m| m.info.mount_point.components().count())
+}
+
+fn device_to_json(device: &BlockDevice, mounts: &[Mount]) -> Value {
+    let mut obj = Map::new();
+    obj.insert("name".into(), device.name.clone().into());
+    obj.insert("mapper-name".into(), device.mapper_name.clone().into());
+    obj.insert("type".into(), device.type_name().into());
+    obj.insert("dev".into(), device.dev.map(|d| d.to_string()).into());
+    obj.insert("size".into(), device.size.into());
+    obj.insert("model".into(), device.model.clone().into());
+    obj.insert("transport".into(), device.transport.into());
+    obj.insert("rotational".into(), device.rotational.into());
+    if let Some(mount) = mount_of(device.dev, mounts) {
+        let mut fs = Map::new();
+        fs.insert("mount-point".into(), mount.info.mount_point.to_string_lossy().into());
+        fs.insert("type".into(), mount.info.fs_type.clone().into());
+        if let Some(stats) = mount.stats() {
+            fs.insert("size".into(), stats.size().into());
+            fs.insert("used".into(), stats.used().into());
+            fs.insert("available".into(), stats.available().into());
+            fs.insert("used-percent".into(), (100.0 * stats.use_share()).into());
+        }
+        obj.insert("filesystem".into(), Value::Object(fs));
+    }
+    let children: Vec<Value> = device.children.iter().map(|c| device_to_json(c, mounts)).collect();
+    obj.insert("children".into(), children.into());
+    Value::Object(obj)
+}
+
+/// A device, with the tree drawing before its name
+struct DeviceLine<'d> {
+    label: String,
+    device: &'d BlockDevice,
+}
+
+fn collect_lines<'d>(
+    devices: &'d [BlockDevice],
+    prefix: &str,
+    ascii: bool,
+    lines: &mut Vec<DeviceLine<'d>>,
+) {
+    let (branch, last_branch, pipe) = if ascii {
+        ("|-- ", "`-- ", "|   ")
+    } else {
+        ("├── ", "└── ", "│   ")
+    };
+    for (i, device) in devices.iter().enumerate() {
+        let is_last = i + 1 == devices.len();
+        lines.push(DeviceLine {
+            label: format!(
+                "{}{}{}",
+                prefix,
+                if is_last { last_branch } else { branch },
+                device.label(),
+            ),
+            device,
+        });
+        let child_prefix = format!("{}{}", prefix, if is_last { "    " } else { pipe });
+        collect_lines(&device.children, &child_prefix, ascii, lines);
+    }
+}
+
+fn write_devices<W: Write>(
+    w: &mut W,
+    devices: &[BlockDevice],
+    mounts: &[Mount],
+    args: &Args,
+) -> io::Result<()> {
+    let mut lines = Vec::new();
+    for device in devices {
+        // the physical devices are the roots, without tree drawing
+        lines.push(DeviceLine {
+            label: device.label().to_string(),
+            device,
+        });
+        collect_lines(&device.children, "", args.ascii, &mut lines);
+    }
+    let mut expander = OwningTemplateExpander::new();
+    expander.set_default("");
+    for line in &lines {
+        let device = line.device;
+        let sub = expander
+            .sub("rows")
+            .set("name", &line.label)
+            .set("type", device.type_name())
+            .set_option("size", device.size.map(|s| args.units.fmt(s)))
+            .set_option("model", device.model.as_ref())
+            .set_option("transport", device.transport)
+            .set_option(
+                "media",
+                device.rotational.map(|r| if r { "HDD" } else { "SSD" }),
+            );
+        if let Some(mount) = mount_of(device.dev, mounts) {
+            sub.set("mount-point", mount.info.mount_point.to_string_lossy());
+            if let Some(stats) = mount.stats() {
+                let pct = 100.0 * stats.use_share() as f32;
+                sub.set("use-percents", format!("{:.0}%", pct))
+                    .set_md("bar", render_bar_compact(pct, 5, args.ascii))
+                    .set("free", args.units.fmt(stats.available()));
+            }
+        }
+    }
+    let mut tbl = TableBuilder::default();
+    tbl.col(Col::new("Device", "${name}").align_content(Alignment::Left))
+        .col(Col::new("Type", "${type}").align_content(Alignment::Left))
+        .col(Col::new("Size", "${size}").align_content(Alignment::Right))
+        .col(Col::new("Model", "${model}").align_content(Alignment::Left))
+        .col(Col::new("Tran", "${transport}").align_content(Alignment::Left))
+        .col(Col::new("Media", "${media}").align_content(Alignment::Center))
+        .col(Col::new("Use", "${use-percents} ${bar}").align_content(Alignment::Right))
+        .col(Col::new("Free", "${free}").align_content(Alignment::Right))
+        .col(Col::new("Mount Point", "${mount-point}").align_content(Alignment::Left));
+    make_skin(args).write_owning_expander_md(w, &expander, &tbl)
+}
@@ -0,0 +1,226 @@
+//! Block device hierarchy
+//!
+//! This module builds the tree of the block devices, from the physical
+//! disks down to the mounted filesystems, as lsblk does.
+
+pub mod display;
+
+pub use display::display_devices;
+
+use {
+    crate::sysfs::Sysfs,
+    lfs_core::DeviceId,
+};
+
+/// Deeper stacks are certainly loops in a broken sysfs
+static MAX_DEPTH: usize = 16;
+
+/// What a block device is, as far as the hierarchy is concerned
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DeviceKind {
+    Disk,
+    Partition,
+    /// LUKS or plain dm-crypt mapping
+    Crypt,
+    /// LVM logical volume
+    Lvm,
+    /// other device-mapper targets, eg multipath
+    DeviceMapper,
+    /// md software RAID
+    Raid,
+    Loop,
+}
+
+impl DeviceKind {
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Disk => "disk",
+            Self::Partition => "part",
+            Self::Crypt => "crypt",
+            Self::Lvm => "lvm",
+            Self::DeviceMapper => "dm",
+            Self::Raid => "raid",
+            Self::Loop => "loop",
+        }
+    }
+}
+
+/// A block device and the devices built on it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BlockDevice {
+    /// kernel name, eg `sda1` or `dm-0`
+    pub name: String,
+    /// device-mapper name, eg `vg-root`, when there's one
+    pub mapper_name: Option<String>,
+    pub kind: DeviceKind,
+    /// RAID level of md devices, eg `raid1`
+    pub raid_level: Option<String>,
+    pub dev: Option<DeviceId>,
+    /// size in bytes
+    pub size: Option<u64>,
+    pub model: Option<String>,
+    pub transport: Option<&'static str>,
+    pub rotational: Option<bool>,
+    /// partitions, then holders
+    pub children: Vec<BlockDevice>,
+}
+
+impl BlockDevice {
+    /// Read the device and, recursively, the devices built on it
+    pub fn read(sysfs: &Sysfs, name: &str) -> Self {
+        Self::read_at_depth(sysfs, name, 0)
+    }
+    fn read_at_depth(sysfs: &Sysfs, name: &str, depth: usize) -> Self {
+        let mapper_name = sysfs.attr(name, "dm/name");
+        let raid_level = sysfs.attr(name, "md/level");
+        let kind = if sysfs.is_partition(name) {
+            DeviceKind::Partition
+        } else if mapper_name.is_some() {
+            let uuid = sysfs.attr(name, "dm/uuid").unwrap_or_default();
+            if uuid.starts_with("CRYPT-") {
+                DeviceKind::Crypt
+            } else if uuid.starts_with("LVM-") {
+                DeviceKind::Lvm
+            } else {
+                DeviceKind::DeviceMapper
+            }
+        } else if raid_level.is_some() {
+            DeviceKind::Raid
+        } else if name.starts_with("loop") {
+            DeviceKind::Loop
+        } else {
+            DeviceKind::Disk
+        };
+        let physical = matches!(kind, DeviceKind::Disk | DeviceKind::Partition);
+        let mut children = Vec::new();
+        if depth < MAX_DEPTH {
+            let partitions = if kind == DeviceKind::Partition {
+                Vec::new()
+            } else {
+                sysfs.partitions(name)
+            };
+            for child in partitions.iter().chain(sysfs.holders(name).iter()) {
+                children.push(Self::read_at_depth(sysfs, child, depth + 1));
+            }
+        }
+        Self {
+            name: name.to_string(),
+            kind,
+            raid_level,
+            dev: sysfs.dev(name),
+            size: sysfs.size(name),
+            model: physical.then(|| sysfs.model(name)).flatten(),
+            transport: physical.then(|| sysfs.transport(name)).flatten(),
+            rotational: sysfs.rotational(name),
+            mapper_name,
+            children,
+        }
+    }
+    /// The name to display: the mapper name when there's one
+    pub fn label(&self) -> &str {
+        self.mapper_name.as_deref().unwrap_or(&self.name)
+    }
+    /// The type to display, with the level for RAID arrays
+    pub fn type_name(&self) -> &str {
+        self.raid_level.as_deref().unwrap_or(self.kind.name())
+    }
+}
+
+/// Read the trees of the block devices, starting from the devices which
+/// aren't built on other ones. Empty devices, like unused loops, and
+/// ram disks are skipped
+pub fn read_devices(sysfs: &Sysfs) -> Vec<BlockDevice> {
+    sysfs
+        .block_devices()
+        .iter()
+        .filter(|name| !name.starts_with("ram") && !name.starts_with("zram"))
+        .filter(|name| sysfs.slaves(name).is_empty())
+        .filter(|name| sysfs.size(name).unwrap_or(0) > 0)
+        .map(|name| BlockDevice::read(sysfs, name))
+        .collect()
+}
+
+#[cfg(unix)]
+#[test]
+fn test_read_devices() {
+    use std::{fs, os::unix::fs::symlink};
+    let root = std::env::temp_dir().join(format!("provis-sysfs-test-{}", std::process::id()));
+    let write = |path: &str, content: &str| {
+        let path = root.join(path);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, content).unwrap();
+    };
+    let mkdir = |path: &str| fs::create_dir_all(root.join(path)).unwrap();
+    // a sata disk with a LUKS partition holding an LVM volume,
+    // and a partition which is a member of a RAID1 array
+    fs::create_dir_all(root.join("devices/pci0000:00/ata1/host0/block/sda")).unwrap();
+    mkdir("block");
+    symlink("../devices/pci0000:00/ata1/host0/block/sda", root.join("block/sda")).unwrap();
+    write("block/sda/dev", "8:0\n");
+    write("block/sda/size", "2000\n");
+    write("block/sda/queue/rotational", "1\n");
+    write("block/sda/device/model", "WDC WD40EFRX   \n");
+    mkdir("block/sda/holders");
+    mkdir("block/sda/slaves");
+    write("block/sda/sda1/partition", "1\n");
+    write("block/sda/sda1/dev", "8:1\n");
+    write("block/sda/sda1/size", "1000\n");
+    mkdir("block/sda/sda1/holders/dm-0");
+    write("block/sda/sda2/partition", "2\n");
+    write("block/sda/sda2/dev", "8:2\n");
+    write("block/sda/sda2/size", "999\n");
+    mkdir("block/sda/sda2/holders/md0");
+    for (name, dev) in [("dm-0", "253:0"), ("dm-1", "253:1"), ("md0", "9:0")] {
+        write(&format!("devices/virtual/block/{}/dev", name), dev);
+        write(&format!("devices/virtual/block/{}/size", name), "900");
+        write(&format!("devices/virtual/block/{}/queue/rotational", name), "1");
+        symlink(format!("../devices/virtual/block/{}", name), root.join("block").join(name)).unwrap();
+    }
+    write("block/dm-0/dm/name", "luks-home");
+    write("block/dm-0/dm/uuid", "CRYPT-LUKS2-0a1b-luks-home");
+    mkdir("block/dm-0/slaves/sda1");
+    mkdir("block/dm-0/holders/dm-1");
+    write("block/dm-1/dm/name", "vg-home");
+    write("block/dm-1/dm/uuid", "LVM-xyz");
+    mkdir("block/dm-1/slaves/dm-0");
+    write("block/md0/md/level", "raid1");
+    mkdir("block/md0/slaves/sda2");
+    mkdir("class/block");
+    mkdir("dev/block");
+    for (name, target, dev) in [
+        ("sda", "../../block/sda", "8:0"),
+        ("sda1", "../../block/sda/sda1", "8:1"),
+        ("sda2", "../../block/sda/sda2", "8:2"),
+        ("dm-0", "../../block/dm-0", "253:0"),
+        ("dm-1", "../../block/dm-1", "253:1"),
+        ("md0", "../../block/md0", "9:0"),
+    ] {
+        symlink(target, root.join("class/block").join(name)).unwrap();
+        symlink(target, root.join("dev/block").join(dev)).unwrap();
+    }
+
+    let sysfs = Sysfs::new(&root);
+    let devices = read_devices(&sysfs);
+    let name_of = sysfs.name_of("253:1".parse().unwrap());
+    let sda1_rotational = sysfs.rotational("sda1");
+    fs::remove_dir_all(&root).unwrap();
+
+    assert_eq!(devices.len(), 1);
+    let sda = &devices[0];
+    assert_eq!(sda.kind, DeviceKind::Disk);
+    assert_eq!(sda.size, Some(2000 * 512));
+    assert_eq!(sda.model.as_deref(), Some("WDC WD40EFRX"));
+    assert_eq!(sda.transport, Some("sata"));
+    assert_eq!(sda.rotational, Some(true));
+    assert_eq!(sda1_rotational, Some(true));
+    assert_eq!(name_of.as_deref(), Some("dm-1"));
+    let labels: Vec<&str> = sda.children.iter().map(BlockDevice::label).collect();
+    assert_eq!(labels, vec!["sda1", "sda2"]);
+    let luks = &sda.children[0].children[0];
+    assert_eq!((luks.label(), luks.kind), ("luks-home", DeviceKind::Crypt));
+    assert_eq!(luks.transport, None);
+    let lvm = &luks.children[0];
+    assert_eq!((lvm.label(), lvm.kind, lvm.dev), ("vg-home", DeviceKind::Lvm, "253:1".parse().ok()));
+    let raid = &sda.children[1].children[0];
+    assert_eq!((raid.type_name(), raid.kind), ("raid1", DeviceKind::Raid));
+}
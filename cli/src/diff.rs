@@ -0,0 +1,588 @@
+use {
+    crate::{
+        Args,
+        directory::display::make_skin,
+        units::Units,
+    },
+    serde_json::{Map, Value, json},
+    std::{
+        collections::{BTreeMap, BTreeSet},
+        fs,
+        io::{self, Write},
+        path::Path,
+    },
+    termimad::minimad::{OwningTemplateExpander, TableBuilder, Col, Alignment},
+};
+
+/// The state of a mount, as read from the JSON of the disk view
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+struct MountState {
+    size: Option<u64>,
+    used: Option<u64>,
+    inodes_used: Option<u64>,
+}
+
+/// The state of a process, as read from the JSON of the process view.
+///
+/// Processes are identified by their name, as the process view
+/// aggregates the processes sharing one
+#[derive(Debug, Clone, Default, PartialEq)]
+struct ProcessState {
+    memory: u64,
+    cpu: f64,
+}
+
+/// A saved JSON output of provis
+#[derive(Debug, Clone, PartialEq)]
+enum Snapshot {
+    /// by mount point
+    Mounts(BTreeMap<String, MountState>),
+    /// the size of the scanned directories, by path
+    Scan {
+        path: String,
+        total_size: u64,
+        directories: BTreeMap<String, u64>,
+        /// whether directories were left out of the list, so that
+        /// a missing one may still exist
+        truncated: bool,
+    },
+    /// by name
+    Processes(BTreeMap<String, ProcessState>),
+}
+
+impl Snapshot {
+    fn read(path: &Path) -> Result<Self, String> {
+        let content = fs::read_to_string(path)
+            .map_err(|e| format!("Error reading {}: {}", path.display(), e))?;
+        let value: Value = serde_json::from_str(&content)
+            .map_err(|e| format!("Invalid JSON in {}: {}", path.display(), e))?;
+        Self::from_value(&value).ok_or_else(|| {
+            format!(
+                "{} isn't the JSON of the disk view, of a directory scan, or of the process view",
+                path.display(),
+            )
+        })
+    }
+    fn from_value(value: &Value) -> Option<Self> {
+        let u64_at = |obj: &Value, key: &str| obj.get(key).and_then(Value::as_u64);
+        if let Value::Object(obj) = value {
+            let directories = obj.get("directories")?.as_array()?;
+            return Some(Self::Scan {
+                path: obj.get("path")?.as_str()?.to_string(),
+                total_size: u64_at(value, "total-size")?,
+                directories: directories
+                    .iter()
+                    .filter_map(|d| Some((d.get("path")?.as_str()?.to_string(), u64_at(d, "size")?)))
+                    .collect(),
+                // scans saved before this flag existed were limited by default
+                truncated: obj.get("truncated").and_then(Value::as_bool).unwrap_or(true),
+            });
+        }
+        let items = value.as_array()?;
+        if items.iter().any(|item| item.get("pid").is_some()) {
+            let processes = items
+                .iter()
+                .filter_map(|p| {
+                    let state = ProcessState {
+                        memory: u64_at(p, "memory").unwrap_or(0),
+                        cpu: p.get("cpu-percent").and_then(Value::as_f64).unwrap_or(0.0),
+                    };
+                    Some((p.get("name")?.as_str()?.to_string(), state))
+                })
+                .collect();
+            return Some(Self::Processes(processes));
+        }
+        // the disk view, which is also the default for an empty array
+        let mounts = items
+            .iter()
+            .filter_map(|m| {
+                let mount_point = m.get("mount-point")?.as_str()?.to_string();
+                let stats = m.get("stats").filter(|s| s.is_object());
+                let state = match stats {
+                    Some(stats) => {
+                        let bsize = u64_at(stats, "bsize");
+                        let blocks = |key: &str| Some(bsize? * u64_at(stats, key)?);
+                        let inodes = stats.get("inodes").filter(|i| i.is_object());
+                        MountState {
+                            size: blocks("blocks"),
+                            used: blocks("bused"),
+                            inodes_used: inodes.and_then(|i| {
+                                Some(u64_at(i, "files")?.saturating_sub(u64_at(i, "free")?))
+                            }),
+                        }
+                    }
+                    None => MountState::default(),
+                };
+                Some((mount_point, state))
+            })
+            .collect();
+        Some(Self::Mounts(mounts))
+    }
+    fn kind(&self) -> &'static str {
+        match self {
+            Self::Mounts(_) => "mounts",
+            Self::Scan { .. } => "directories",
+            Self::Processes(_) => "processes",
+        }
+    }
+}
+
+/// How a value changed between the two snapshots
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Delta {
+    before: Option<u64>,
+    after: Option<u64>,
+}
+
+impl Delta {
+    /// The change, unknown when the value is missing on one side
+    fn change(self) -> Option<i64> {
+        Some(self.after? as i64 - self.before? as i64)
+    }
+    fn is_change(self) -> bool {
+        self.change().is_some_and(|change| change != 0)
+    }
+    fn to_json(self) -> Value {
+        json!({
+            "before": self.before,
+            "after": self.after,
+            "change": self.change(),
+        })
+    }
+}
+
+/// The entries present in only one of the snapshots, then
+/// those present in both
+fn split_keys<'m, T>(
+    before: &'m BTreeMap<String, T>,
+    after: &'m BTreeMap<String, T>,
+) -> (Vec<&'m str>, Vec<&'m str>, Vec<&'m str>) {
+    let keys: BTreeSet<&str> = before.keys().chain(after.keys()).map(String::as_str).collect();
+    let mut appeared = Vec::new();
+    let mut disappeared = Vec::new();
+    let mut common = Vec::new();
+    for key in keys {
+        match (before.contains_key(key), after.contains_key(key)) {
+            (false, _) => appeared.push(key),
+            (_, false) => disappeared.push(key),
+            _ => common.push(key),
+        }
+    }
+    (appeared, disappeared, common)
+}
+
+/// Compare two JSON outputs of provis, of the same view
+pub fn display_diff<W: Write>(
+    w: &mut W,
+    before: &Path,
+    after: &Path,
+    args: &Args,
+) -> io::Result<()> {
+    let (before, after) = match (Snapshot::read(before), Snapshot::read(after)) {
+        (Ok(before), Ok(after)) => (before, after),
+        (Err(e), _) | (_, Err(e)) => {
+            eprintln!("{}", e);
+            return Ok(());
+        }
+    };
+    let limit = args.limit.or_max();
+    match (&before, &after) {
+        (Snapshot::Mounts(before), Snapshot::Mounts(after)) => {
+            diff_mounts(w, before, after, args)
+        }
+        (
+            Snapshot::Scan { path, total_size, directories, truncated },
+            Snapshot::Scan {
+                path: after_path,
+                total_size: after_total,
+                directories: after_dirs,
+                truncated: after_truncated,
+            },
+        ) => {
+            if path != after_path {
+                eprintln!("Warning: comparing scans of {} and {}", path, after_path);
+            }
+            let complete = !truncated && !after_truncated;
+            if !complete {
+                eprintln!(
+                    "Warning: the scans don't list all directories, so the change of a directory \
+                    listed in only one of them is unknown - scan with `--limit all` to compare them all"
+                );
+            }
+            let total = Delta { before: Some(*total_size), after: Some(*after_total) };
+            let changes = biggest_changes(directories, after_dirs, complete, limit);
+            diff_directories(w, after_path, total, &changes, args)
+        }
+        (Snapshot::Processes(before), Snapshot::Processes(after)) => {
+            diff_processes(w, before, after, limit, args)
+        }
+        _ => {
+            eprintln!(
+                "Can't compare a snapshot of {} with one of {}",
+                before.kind(),
+                after.kind(),
+            );
+            Ok(())
+        }
+    }
+}
+
+fn diff_mounts<W: Write>(
+    w: &mut W,
+    before: &BTreeMap<String, MountState>,
+    after: &BTreeMap<String, MountState>,
+    args: &Args,
+) -> io::Result<()> {
+    let (appeared, disappeared, common) = split_keys(before, after);
+    let changed: Vec<(&str, Delta, Delta, Delta)> = common
+        .into_iter()
+        .map(|mp| {
+            let (b, a) = (&before[mp], &after[mp]);
+            (
+                mp,
+                Delta { before: b.size, after: a.size },
+                Delta { before: b.used, after: a.used },
+                Delta { before: b.inodes_used, after: a.inodes_used },
+            )
+        })
+        .filter(|(_, size, used, inodes)| size.is_change() || used.is_change() || inodes.is_change())
+        .collect();
+    if args.json {
+        let changed: Vec<Value> = changed
+            .iter()
+            .map(|(mp, size, used, inodes)| {
+                json!({
+                    "mount-point": mp,
+                    "size": size.to_json(),
+                    "used": used.to_json(),
+                    "inodes-used": inodes.to_json(),
+                })
+            })
+            .collect();
+        let value = json!({
+            "kind": "mounts",
+            "appeared": appeared,
+            "disappeared": disappeared,
+            "changed": changed,
+        });
+        return writeln!(w, "{}", serde_json::to_string_pretty(&value).unwrap());
+    }
+    if appeared.is_empty() && disappeared.is_empty() && changed.is_empty() {
+        return writeln!(w, "No change in the mounts");
+    }
+    let units = args.units;
+    let mut expander = OwningTemplateExpander::new();
+    expander.set_default("");
+    for mp in &appeared {
+        let state = &after[*mp];
+        expander
+            .sub("rows")
+            .set("mount-point", mp)
+            .set("status", "new")
+            .set_option("size", state.size.map(|s| units.fmt(s)))
+            .set_option("used", state.used.map(|s| units.fmt(s)));
+    }
+    for mp in &disappeared {
+        let state = &before[*mp];
+        expander
+            .sub("rows")
+            .set("mount-point", mp)
+            .set("status", "gone")
+            .set_option("size", state.size.map(|s| units.fmt(s)))
+            .set_option("used", state.used.map(|s| units.fmt(s)));
+    }
+    for (mp, size, used, inodes) in &changed {
+        let sub = expander
+            .sub("rows")
+            .set("mount-point", mp)
+            .set_option("size", size.after.map(|s| units.fmt(s)))
+            .set_option("used", used.after.map(|s| units.fmt(s)));
+        if let Some(change) = size.change().filter(|&c| c != 0) {
+            sub.set("size-change", units.fmt_signed(change));
+        }
+        if let Some(change) = used.change().filter(|&c| c != 0) {
+            sub.set("used-change", units.fmt_signed(change));
+        }
+        if let Some(change) = inodes.change().filter(|&c| c != 0) {
+            sub.set("inodes-change", format!("{:+}", change));
+        }
+    }
+    let mut tbl = TableBuilder::default();
+    tbl.col(Col::new("Mount Point", "${mount-point}").align_content(Alignment::Left))
+        .col(Col::new("Status", "${status}").align_content(Alignment::Center))
+        .col(Col::new("Size", "${size}").align_content(Alignment::Right))
+        .col(Col::new("Size Change", "${size-change}").align_content(Alignment::Right))
+        .col(Col::new("Used", "${used}").align_content(Alignment::Right))
+        .col(Col::new("Used Change", "${used-change}").align_content(Alignment::Right))
+        .col(Col::new("Inodes Change", "${inodes-change}").align_content(Alignment::Right));
+    make_skin(args).write_owning_expander_md(w, &expander, &tbl)
+}
+
+/// An entry, eg a directory, with its change
+type Change<'m> = (&'m str, Delta);
+
+/// The changes between two snapshots, the biggest ones first
+#[derive(Debug, Default)]
+struct Changes<'m> {
+    grown: Vec<Change<'m>>,
+    shrunk: Vec<Change<'m>>,
+    /// entries missing from a snapshot which doesn't list everything
+    unknown: Vec<Change<'m>>,
+}
+
+/// Sort the entries by change. When the snapshots are `complete`, an entry
+/// missing from one of them doesn't exist there, otherwise its change is unknown
+fn biggest_changes<'m>(
+    before: &'m BTreeMap<String, u64>,
+    after: &'m BTreeMap<String, u64>,
+    complete: bool,
+    limit: usize,
+) -> Changes<'m> {
+    let (appeared, disappeared, common) = split_keys(before, after);
+    let missing = if complete { Some(0) } else { None };
+    let mut changes = Changes::default();
+    for key in appeared.into_iter().chain(disappeared).chain(common) {
+        let delta = Delta {
+            before: before.get(key).copied().or(missing),
+            after: after.get(key).copied().or(missing),
+        };
+        match delta.change() {
+            Some(0) => {}
+            Some(change) if change > 0 => changes.grown.push((key, delta)),
+            Some(_) => changes.shrunk.push((key, delta)),
+            None => changes.unknown.push((key, delta)),
+        }
+    }
+    changes.grown.sort_by_key(|(_, delta)| std::cmp::Reverse(delta.change()));
+    changes.shrunk.sort_by_key(|(_, delta)| delta.change());
+    changes
+        .unknown
+        .sort_by_key(|(_, delta)| std::cmp::Reverse(delta.before.or(delta.after)));
+    changes.grown.truncate(limit);
+    changes.shrunk.truncate(limit);
+    changes.unknown.truncate(limit);
+    changes
+}
+
+fn diff_directories<W: Write>(
+    w: &mut W,
+    path: &str,
+    total: Delta,
+    changes: &Changes,
+    args: &Args,
+) -> io::Result<()> {
+    if args.json {
+        let entries = |entries: &[Change]| -> Vec<Value> {
+            entries
+                .iter()
+                .map(|(path, delta)| {
+                    let mut obj = Map::new();
+                    obj.insert("path".into(), (*path).into());
+                    if let Value::Object(delta) = delta.to_json() {
+                        obj.extend(delta);
+                    }
+                    Value::Object(obj)
+                })
+                .collect()
+        };
+        let value = json!({
+            "kind": "directories",
+            "path": path,
+            "total-size": total.to_json(),
+            "grown": entries(&changes.grown),
+            "shrunk": entries(&changes.shrunk),
+            "unknown": entries(&changes.unknown),
+        });
+        return writeln!(w, "{}", serde_json::to_string_pretty(&value).unwrap());
+    }
+    writeln!(
+        w,
+        "\n{}: {} ({})",
+        path,
+        args.units.fmt(total.after.unwrap_or(0)),
+        args.units.fmt_signed(total.change().unwrap_or(0)),
+    )?;
+    if changes.grown.is_empty() && changes.shrunk.is_empty() && changes.unknown.is_empty() {
+        return writeln!(w, "No change in the directories");
+    }
+    let skin = make_skin(args);
+    for (title, entries) in [
+        ("Grown Directories", &changes.grown),
+        ("Shrunk Directories", &changes.shrunk),
+        ("Directories Listed in Only One Scan", &changes.unknown),
+    ] {
+        if entries.is_empty() {
+            continue;
+        }
+        writeln!(w, "\n{}:\n", title)?;
+        let mut expander = OwningTemplateExpander::new();
+        expander.set_default("");
+        for (path, delta) in entries {
+            add_delta_row(&mut expander, path, *delta, args.units);
+        }
+        let tbl = delta_table("Directory");
+        skin.write_owning_expander_md(w, &expander, &tbl)?;
+    }
+    Ok(())
+}
+
+fn diff_processes<W: Write>(
+    w: &mut W,
+    before: &BTreeMap<String, ProcessState>,
+    after: &BTreeMap<String, ProcessState>,
+    limit: usize,
+    args: &Args,
+) -> io::Result<()> {
+    let (new, exited, _) = split_keys(before, after);
+    let memory = |processes: &BTreeMap<String, ProcessState>| -> BTreeMap<String, u64> {
+        processes
+            .iter()
+            .filter(|(name, _)| before.contains_key(*name) && after.contains_key(*name))
+            .map(|(name, p)| (name.clone(), p.memory))
+            .collect()
+    };
+    let (before_memory, after_memory) = (memory(before), memory(after));
+    let grown = biggest_changes(&before_memory, &after_memory, true, limit).grown;
+    if args.json {
+        let process = |name: &str, p: &ProcessState| {
+            json!({ "name": name, "memory": p.memory, "cpu-percent": p.cpu })
+        };
+        let new: Vec<Value> = new.iter().map(|n| process(n, &after[*n])).collect();
+        let exited: Vec<Value> = exited.iter().map(|n| process(n, &before[*n])).collect();
+        let grown: Vec<Value> = grown
+            .iter()
+            .map(|(name, delta)| json!({ "name": name, "memory": delta.to_json() }))
+            .collect();
+        let value = json!({
+            "kind": "processes",
+            "new": new,
+            "exited": exited,
+            "grown": grown,
+        });
+        return writeln!(w, "{}", serde_json::to_string_pretty(&value).unwrap());
+    }
+    if new.is_empty() && exited.is_empty() && grown.is_empty() {
+        return writeln!(w, "No change in the processes");
+    }
+    let skin = make_skin(args);
+    let units = args.units;
+    for (title, names, processes) in [("New", &new, after), ("Exited", &exited, before)] {
+        if names.is_empty() {
+            continue;
+        }
+        writeln!(w, "\n{} Processes:\n", title)?;
+        let mut by_memory: Vec<(&str, &ProcessState)> =
+            names.iter().map(|n| (*n, &processes[*n])).collect();
+        by_memory.sort_by_key(|(_, p)| std::cmp::Reverse(p.memory));
+        let mut expander = OwningTemplateExpander::new();
+        for (name, p) in by_memory.iter().take(limit) {
+            expander
+                .sub("rows")
+                .set("name", name)
+                .set("memory", units.fmt(p.memory))
+                .set("cpu", format!("{:.1}%", p.cpu));
+        }
+        let mut tbl = TableBuilder::default();
+        tbl.col(Col::new("Process", "${name}").align_content(Alignment::Left))
+            .col(Col::new("Memory", "${memory}").align_content(Alignment::Right))
+            .col(Col::new("CPU%", "${cpu}").align_content(Alignment::Right));
+        skin.write_owning_expander_md(w, &expander, &tbl)?;
+    }
+    if !grown.is_empty() {
+        writeln!(w, "\nGrown Processes (memory):\n")?;
+        let mut expander = OwningTemplateExpander::new();
+        expander.set_default("");
+        for (name, delta) in &grown {
+            add_delta_row(&mut expander, name, *delta, units);
+        }
+        skin.write_owning_expander_md(w, &expander, &delta_table("Process"))?;
+    }
+    Ok(())
+}
+
+fn add_delta_row(
+    expander: &mut OwningTemplateExpander,
+    name: &str,
+    delta: Delta,
+    units: Units,
+) {
+    expander
+        .sub("rows")
+        .set("name", name)
+        .set("before", delta.before.map_or("-".to_string(), |v| units.fmt(v)))
+        .set("after", delta.after.map_or("-".to_string(), |v| units.fmt(v)))
+        .set("change", delta.change().map_or("?".to_string(), |c| units.fmt_signed(c)));
+}
+
+fn delta_table(title: &str) -> TableBuilder {
+    let mut tbl = TableBuilder::default();
+    tbl.col(Col::new(title, "${name}").align_content(Alignment::Left))
+        .col(Col::new("Before", "${before}").align_content(Alignment::Right))
+        .col(Col::new("After", "${after}").align_content(Alignment::Right))
+        .col(Col::new("Change", "${change}").align_content(Alignment::Right));
+    tbl
+}
+
+#[test]
+fn test_diff_snapshots() {
+    let mounts = |used: u64, extra: bool| {
+        let mut items = vec![json!({
+            "mount-point": "/var",
+            "stats": {
+                "bsize": 4096,
+                "blocks": 1000,
+                "bused": used,
+                "inodes": { "files": 100, "free": 40 },
+            },
+        })];
+        if extra {
+            items.push(json!({ "mount-point": "/mnt/usb", "stats": null }));
+        }
+        Snapshot::from_value(&Value::Array(items)).unwrap()
+    };
+    let Snapshot::Mounts(before) = mounts(200, true) else {
+        panic!("not mounts");
+    };
+    let Snapshot::Mounts(after) = mounts(250, false) else {
+        panic!("not mounts");
+    };
+    assert_eq!(before["/var"].inodes_used, Some(60));
+    assert_eq!(before["/mnt/usb"], MountState::default());
+    let (appeared, disappeared, common) = split_keys(&before, &after);
+    assert!(appeared.is_empty());
+    assert_eq!(disappeared, vec!["/mnt/usb"]);
+    assert_eq!(common, vec!["/var"]);
+    let used = Delta { before: before["/var"].used, after: after["/var"].used };
+    assert_eq!(used.change(), Some(50 * 4096));
+    // /mnt/usb had no stats: its size change isn't known
+    assert_eq!(Delta { before: None, after: Some(10) }.change(), None);
+
+    let scan = Snapshot::from_value(&json!({
+        "path": "/srv",
+        "total-size": 300,
+        "truncated": false,
+        "directories": [{ "path": "/srv/a", "size": 100 }, { "path": "/srv/b", "size": 200 }],
+    }));
+    let Some(Snapshot::Scan { directories: before, truncated: false, .. }) = scan else {
+        panic!("not a complete scan");
+    };
+    let after: BTreeMap<String, u64> = [("/srv/a".to_string(), 50), ("/srv/c".to_string(), 500)]
+        .into_iter()
+        .collect();
+    let changes = biggest_changes(&before, &after, true, 10);
+    assert_eq!(changes.grown, vec![("/srv/c", Delta { before: Some(0), after: Some(500) })]);
+    let shrunk: Vec<(&str, Option<i64>)> = changes.shrunk.iter().map(|(p, d)| (*p, d.change())).collect();
+    assert_eq!(shrunk, vec![("/srv/b", Some(-200)), ("/srv/a", Some(-50))]);
+    assert!(changes.unknown.is_empty());
+    // in top-N scans, /srv/b may just have dropped below the listed ones
+    let changes = biggest_changes(&before, &after, false, 10);
+    assert!(changes.grown.is_empty());
+    assert_eq!(changes.shrunk, vec![("/srv/a", Delta { before: Some(100), after: Some(50) })]);
+    let unknown: Vec<&str> = changes.unknown.iter().map(|(p, _)| *p).collect();
+    assert_eq!(unknown, vec!["/srv/c", "/srv/b"]);
+    let old_scan = json!({ "path": "/srv", "total-size": 0, "directories": [] });
+    assert!(matches!(Snapshot::from_value(&old_scan), Some(Snapshot::Scan { truncated: true, .. })));
+
+    let processes = Snapshot::from_value(&json!([{ "pid": 1, "name": "init", "memory": 10 }]));
+    assert!(matches!(processes, Some(Snapshot::Processes(p)) if p["init"].memory == 10));
+}
@@ -0,0 +1,107 @@
+use {
+    crate::{
+        Args,
+        age::AgeBucket,
+        directory::{
+            TypeStats,
+            display::{format_bytes, make_skin},
+        },
+        process::graph::render_bar_compact,
+    },
+    serde_json::{Value, json},
+    std::{
+        io::{self, Write},
+        time::Duration,
+    },
+    termimad::minimad::{OwningTemplateExpander, TableBuilder, Col, Alignment},
+};
+
+/// Scanned bytes, bucketed by time since last modification and
+/// time since last access
+#[derive(Debug, Clone, Default)]
+pub struct AgeBreakdown {
+    pub modified: [TypeStats; 5],
+    pub accessed: [TypeStats; 5],
+}
+
+impl AgeBreakdown {
+    pub fn add(
+        &mut self,
+        size: u64,
+        modified_age: Option<Duration>,
+        accessed_age: Option<Duration>,
+    ) {
+        for (buckets, age) in [
+            (&mut self.modified, modified_age),
+            (&mut self.accessed, accessed_age),
+        ] {
+            if let Some(age) = age {
+                let stats = &mut buckets[AgeBucket::of(age).index()];
+                stats.bytes += size;
+                stats.files += 1;
+            }
+        }
+    }
+    pub fn total_bytes(&self) -> u64 {
+        self.modified.iter().map(|s| s.bytes).sum()
+    }
+    pub fn to_json(&self) -> Value {
+        let rows = |buckets: &[TypeStats; 5]| -> Value {
+            Value::Array(
+                AgeBucket::ALL
+                    .iter()
+                    .map(|b| {
+                        let s = buckets[b.index()];
+                        json!({
+                            "age": b.label(),
+                            "bytes": s.bytes,
+                            "files": s.files,
+                        })
+                    })
+                    .collect(),
+            )
+        };
+        json!({
+            "total-bytes": self.total_bytes(),
+            "modified": rows(&self.modified),
+            "accessed": rows(&self.accessed),
+        })
+    }
+}
+
+/// Write the age table, with one row per age bucket
+pub fn write_ages<W: Write>(
+    w: &mut W,
+    ages: &AgeBreakdown,
+    args: &Args,
+) -> io::Result<()> {
+    let total = ages.total_bytes().max(1);
+    let mut expander = OwningTemplateExpander::new();
+    expander.set_default("");
+    for bucket in AgeBucket::ALL {
+        let modified = ages.modified[bucket.index()];
+        let accessed = ages.accessed[bucket.index()];
+        let m_pct = 100.0 * modified.bytes as f32 / total as f32;
+        let a_pct = 100.0 * accessed.bytes as f32 / total as f32;
+        expander
+            .sub("rows")
+            .set("age", bucket.label())
+            .set_md("m_bar", render_bar_compact(m_pct, 10, args.ascii))
+            .set("m_size", format_bytes(modified.bytes))
+            .set("m_files", modified.files)
+            .set_md("a_bar", render_bar_compact(a_pct, 10, args.ascii))
+            .set("a_size", format_bytes(accessed.bytes))
+            .set("a_files", accessed.files);
+    }
+    let mut tbl = TableBuilder::default();
+    tbl.col(Col::new("Age", "${age}").align_content(Alignment::Left))
+        .col(Col::new("Modified", "${m_bar}").align_content(Alignment::Left))
+        .col(Col::new("Size", "${m_size}").align_content(Alignment::Right))
+        .col(Col::new("Files", "${m_files}").align_content(Alignment::Right))
+        .col(Col::new("Accessed", "${a_bar}").align_content(Alignment::Left))
+        .col(Col::new("Size", "${a_size}").align_content(Alignment::Right))
+        .col(Col::new("Files", "${a_files}").align_content(Alignment::Right));
+    writeln!(w, "\nScanned Space by Age:\n")?;
+    make_skin(args).write_owning_expander_md(w, &expander, &tbl)?;
+    writeln!(w, "(access times may be approximate on relatime or noatime mounts)")
+}
@@ -0,0 +1,337 @@
+use {
+    crate::{
+        Args,
+        age::Age,
+        directory::{
+            display::{format_bytes, make_skin},
+            output::insert_path,
+        },
+        process::graph::render_bar_compact,
+    },
+    serde_json::{Map, Value, json},
+    std::{
+        io::{self, Write},
+        path::{Component, Path, PathBuf},
+        time::{SystemTime, UNIX_EPOCH},
+    },
+    termimad::minimad::{OwningTemplateExpander, TableBuilder, Col, Alignment},
+};
+
+/// Package manager caches, as path suffixes, usually below a home directory
+static PACKAGE_CACHES: &[&[&str]] = &[
+    &[".cargo", "registry"],
+    &[".cargo", "git"],
+    &[".npm"],
+    &[".cache", "pip"],
+    &[".cache", "pypoetry"],
+    &[".cache", "yarn"],
+    &[".yarn", "cache"],
+    &[".cache", "go-build"],
+    &[".gradle", "caches"],
+    &[".m2", "repository"],
+    &[".local", "share", "pnpm"],
+];
+
+/// Directories of projects which can be rebuilt, by name, with the kind of
+/// artifact they are and the files which tell it, relative to the directory
+/// holding them. A directory is only an artifact when one of its markers
+/// exists, so that eg a `vendor` or `build` directory out of a project isn't
+/// reported. Names which are never used for anything else have no marker.
+static PROJECT_ARTIFACTS: &[(&str, ArtifactKind, &[&str])] = &[
+    ("node_modules", ArtifactKind::Dependencies, &["package.json"]),
+    ("vendor", ArtifactKind::Dependencies, &["composer.json", "go.mod", "Gemfile"]),
+    ("target", ArtifactKind::RustTarget, &["Cargo.toml"]),
+    (
+        "build",
+        ArtifactKind::BuildOutput,
+        &["build.gradle", "build.gradle.kts", "CMakeLists.txt", "package.json", "setup.py", "pyproject.toml"],
+    ),
+    ("dist", ArtifactKind::BuildOutput, &["package.json", "setup.py", "pyproject.toml"]),
+    ("__pycache__", ArtifactKind::PythonCache, &[]),
+    (".pytest_cache", ArtifactKind::PythonCache, &[]),
+    (".mypy_cache", ArtifactKind::PythonCache, &[]),
+    ("venv", ArtifactKind::PythonEnv, &["venv/pyvenv.cfg"]),
+    (".venv", ArtifactKind::PythonEnv, &[".venv/pyvenv.cfg"]),
+    (".tox", ArtifactKind::PythonEnv, &["tox.ini", "pyproject.toml", "setup.cfg"]),
+    ("coverage", ArtifactKind::Coverage, &["package.json"]),
+    ("htmlcov", ArtifactKind::Coverage, &[".coveragerc", "pyproject.toml", "setup.cfg", "setup.py", "tox.ini"]),
+];
+
+/// A kind of directory holding data which can be downloaded or built again
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ArtifactKind {
+    /// dependencies of a project, eg `node_modules`
+    Dependencies,
+    /// rust's `target`
+    RustTarget,
+    BuildOutput,
+    PythonCache,
+    /// virtual environments, including tox's
+    PythonEnv,
+    Coverage,
+    PackageCache,
+}
+
+impl ArtifactKind {
+    pub const ALL: [Self; 7] = [
+        Self::Dependencies,
+        Self::RustTarget,
+        Self::BuildOutput,
+        Self::PythonCache,
+        Self::PythonEnv,
+        Self::Coverage,
+        Self::PackageCache,
+    ];
+    /// Determine the kind of a directory: a package cache, recognized by
+    /// the end of its path, or a project artifact, recognized by its name
+    /// and a marker of its project
+    pub fn of_dir(path: &Path) -> Option<Self> {
+        if package_cache_root(path).is_some() {
+            return Some(Self::PackageCache);
+        }
+        let name = path.file_name()?.to_str()?;
+        let project = path.parent()?;
+        PROJECT_ARTIFACTS
+            .iter()
+            .find(|(artifact_name, _, markers)| {
+                *artifact_name == name
+                    && (markers.is_empty() || markers.iter().any(|m| project.join(m).exists()))
+            })
+            .map(|&(_, kind, _)| kind)
+    }
+    fn index(self) -> usize {
+        match self {
+            Self::Dependencies => 0,
+            Self::RustTarget => 1,
+            Self::BuildOutput => 2,
+            Self::PythonCache => 3,
+            Self::PythonEnv => 4,
+            Self::Coverage => 5,
+            Self::PackageCache => 6,
+        }
+    }
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Dependencies => "dependencies",
+            Self::RustTarget => "rust-target",
+            Self::BuildOutput => "build-output",
+            Self::PythonCache => "python-cache",
+            Self::PythonEnv => "python-env",
+            Self::Coverage => "coverage",
+            Self::PackageCache => "package-cache",
+        }
+    }
+}
+
+/// If `path` is a package cache, return the directory holding
+/// it, eg the home directory for `~/.cargo/registry`
+fn package_cache_root(path: &Path) -> Option<&Path> {
+    let components: Vec<Component> = path.components().collect();
+    PACKAGE_CACHES.iter().find_map(|suffix| {
+        let start = components.len().checked_sub(suffix.len())?;
+        let matches = components[start..]
+            .iter()
+            .zip(suffix.iter())
+            .all(|(c, s)| c.as_os_str() == *s);
+        if !matches {
+            return None;
+        }
+        let mut root = path;
+        for _ in 0..suffix.len() {
+            root = root.parent()?;
+        }
+        Some(root)
+    })
+}
+
+/// A directory which could be deleted to reclaim space
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Artifact {
+    pub path: PathBuf,
+    pub kind: ArtifactKind,
+    pub size: u64,
+    pub files: u64,
+    /// most recent modification of the files of the artifact
+    pub modified: Option<SystemTime>,
+    /// the project, or home directory, the artifact belongs to
+    pub project: PathBuf,
+    /// most recent modification in the project, outside its artifacts
+    pub project_modified: Option<SystemTime>,
+}
+
+impl Artifact {
+    pub fn new(path: PathBuf, kind: ArtifactKind) -> Self {
+        let project = match kind {
+            ArtifactKind::PackageCache => package_cache_root(&path),
+            _ => path.parent(),
+        };
+        let project = project.unwrap_or(&path).to_path_buf();
+        Self {
+            path,
+            kind,
+            size: 0,
+            files: 0,
+            modified: None,
+            project,
+            project_modified: None,
+        }
+    }
+}
+
+/// Sizes of the artifacts, in bytes, by kind
+pub fn sizes_by_kind(artifacts: &[Artifact]) -> Vec<(ArtifactKind, u64, usize)> {
+    let mut sums = [(0u64, 0usize); 7];
+    for artifact in artifacts {
+        let sum = &mut sums[artifact.kind.index()];
+        sum.0 += artifact.size;
+        sum.1 += 1;
+    }
+    let mut sizes: Vec<(ArtifactKind, u64, usize)> = ArtifactKind::ALL
+        .iter()
+        .map(|&kind| (kind, sums[kind.index()].0, sums[kind.index()].1))
+        .filter(|&(_, _, count)| count > 0)
+        .collect();
+    sizes.sort_by_key(|&(_, size, _)| std::cmp::Reverse(size));
+    sizes
+}
+
+pub fn artifacts_to_json(artifacts: &[Artifact], limit: usize) -> Value {
+    let total: u64 = artifacts.iter().map(|a| a.size).sum();
+    let categories: Vec<Value> = sizes_by_kind(artifacts)
+        .iter()
+        .map(|(kind, size, count)| {
+            json!({
+                "category": kind.name(),
+                "size": size,
+                "dirs": count,
+            })
+        })
+        .collect();
+    let list: Vec<Value> = artifacts
+        .iter()
+        .take(limit)
+        .map(|a| {
+            let mut obj = Map::new();
+            insert_path(&mut obj, "path", &a.path);
+            obj.insert("category".into(), a.kind.name().into());
+            obj.insert("size".into(), a.size.into());
+            obj.insert("files".into(), a.files.into());
+            obj.insert("modified".into(), unix_seconds(a.modified));
+            insert_path(&mut obj, "project", &a.project);
+            obj.insert("project-modified".into(), unix_seconds(a.project_modified));
+            Value::Object(obj)
+        })
+        .collect();
+    json!({
+        "total-size": total,
+        "categories": categories,
+        "artifacts": list,
+    })
+}
+
+fn unix_seconds(time: Option<SystemTime>) -> Value {
+    time.and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+        .map_or(Value::Null, |d| d.as_secs().into())
+}
+
+/// Write the space by category then the biggest artifacts,
+/// which must be sorted biggest first
+pub fn write_reclaimable<W: Write>(
+    w: &mut W,
+    artifacts: &[Artifact],
+    limit: usize,
+    args: &Args,
+) -> io::Result<()> {
+    if artifacts.is_empty() {
+        return writeln!(w, "\nNo caches or build artifacts found.");
+    }
+    let total: u64 = artifacts.iter().map(|a| a.size).sum();
+    let now = SystemTime::now();
+    let age = |time: Option<SystemTime>| {
+        time.map(|t| Age(now.duration_since(t).unwrap_or_default()).to_string())
+    };
+
+    let mut expander = OwningTemplateExpander::new();
+    expander.set_default("");
+    for (kind, size, count) in sizes_by_kind(artifacts) {
+        let pct = if total == 0 { 0.0 } else { 100.0 * size as f32 / total as f32 };
+        expander
+            .sub("rows")
+            .set_md("bar", render_bar_compact(pct, 10, args.ascii))
+            .set("size", format_bytes(size))
+            .set("count", count)
+            .set("name", kind.name());
+    }
+    let mut tbl = TableBuilder::default();
+    tbl.col(Col::new("Usage", "${bar}").align_content(Alignment::Left))
+        .col(Col::new("Size", "${size}").align_content(Alignment::Right))
+        .col(Col::new("Dirs", "${count}").align_content(Alignment::Right))
+        .col(Col::new("Category", "${name}").align_content(Alignment::Left));
+    writeln!(w, "\nReclaimable Space by Category ({} in total):\n", format_bytes(total))?;
+    make_skin(args).write_owning_expander_md(w, &expander, &tbl)?;
+
+    let limit = limit.min(artifacts.len());
+    let max_size = artifacts[0].size.max(1);
+    let mut expander = OwningTemplateExpander::new();
+    expander.set_default("");
+    for artifact in &artifacts[..limit] {
+        let pct = 100.0 * artifact.size as f32 / max_size as f32;
+        let name = artifact
+            .path
+            .strip_prefix(&artifact.project)
+            .unwrap_or(&artifact.path);
+        expander
+            .sub("rows")
+            .set_md("bar", render_bar_compact(pct, 10, args.ascii))
+            .set("size", format_bytes(artifact.size))
+            .set("category", artifact.kind.name())
+            .set("name", name.display())
+            .set("project", artifact.project.display())
+            .set_option("project_modified", age(artifact.project_modified));
+    }
+    let mut tbl = TableBuilder::default();
+    tbl.col(Col::new("Usage", "${bar}").align_content(Alignment::Left))
+        .col(Col::new("Size", "${size}").align_content(Alignment::Right))
+        .col(Col::new("Category", "${category}").align_content(Alignment::Left))
+        .col(Col::new("Artifact", "${name}").align_content(Alignment::Left))
+        .col(Col::new("Project", "${project}").align_content(Alignment::Left))
+        .col(Col::new("Last Edit", "${project_modified}").align_content(Alignment::Right));
+    writeln!(w, "\nTop {} Caches and Build Artifacts:\n", limit)?;
+    make_skin(args).write_owning_expander_md(w, &expander, &tbl)
+}
+
+#[test]
+fn test_artifact_kinds() {
+    use std::fs;
+    let dir = std::env::temp_dir().join(format!("provis-artifacts-test-{}", std::process::id()));
+    let app = dir.join("app");
+    for sub in ["node_modules", "target", "bin", "build", "venv", ".tox", "__pycache__", "src"] {
+        fs::create_dir_all(app.join(sub)).unwrap();
+    }
+    fs::create_dir_all(dir.join("usr/vendor")).unwrap();
+    fs::write(app.join("package.json"), "{}").unwrap();
+    fs::write(app.join("tox.ini"), "").unwrap();
+    let of_dir = |path: &str| ArtifactKind::of_dir(&dir.join(path));
+    assert_eq!(of_dir("app/node_modules"), Some(ArtifactKind::Dependencies));
+    assert_eq!(of_dir("app/build"), Some(ArtifactKind::BuildOutput));
+    assert_eq!(of_dir("app/.tox"), Some(ArtifactKind::PythonEnv));
+    assert_eq!(of_dir("app/__pycache__"), Some(ArtifactKind::PythonCache));
+    // no Cargo.toml, no pyvenv.cfg
+    assert_eq!(of_dir("app/target"), None);
+    assert_eq!(of_dir("app/venv"), None);
+    assert_eq!(of_dir("app/bin"), None);
+    assert_eq!(of_dir("app/src"), None);
+    assert_eq!(of_dir("usr/vendor"), None);
+    fs::write(app.join("Cargo.toml"), "").unwrap();
+    fs::write(app.join("venv/pyvenv.cfg"), "").unwrap();
+    assert_eq!(of_dir("app/target"), Some(ArtifactKind::RustTarget));
+    assert_eq!(of_dir("app/venv"), Some(ArtifactKind::PythonEnv));
+    fs::remove_dir_all(&dir).unwrap();
+    assert_eq!(ArtifactKind::of_dir(Path::new("/home/u/.cargo")), None);
+    let registry = Path::new("/home/u/.cargo/registry");
+    assert_eq!(ArtifactKind::of_dir(registry), Some(ArtifactKind::PackageCache));
+    let artifact = Artifact::new(registry.to_path_buf(), ArtifactKind::PackageCache);
+    assert_eq!(artifact.project, Path::new("/home/u"));
+    let artifact = Artifact::new("/src/app/target".into(), ArtifactKind::RustTarget);
+    assert_eq!(artifact.project, Path::new("/src/app"));
+}
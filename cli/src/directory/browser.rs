@@ -0,0 +1,509 @@
+use {
+    crate::{
+        age::Age,
+        directory::{DirNode, display::format_bytes},
+    },
+    std::{
+        cmp::Reverse,
+        fs,
+        io::{self, Write},
+        path::{Path, PathBuf},
+        time::SystemTime,
+    },
+    termimad::{
+        ProgressBar,
+        crossterm::{
+            cursor,
+            event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers},
+            execute,
+            queue,
+            style::{Attribute, Print, SetAttribute},
+            terminal::{self, ClearType, EnterAlternateScreen, LeaveAlternateScreen},
+        },
+    },
+};
+
+static BAR_WIDTH: usize = 10;
+
+/// How the entries of the browsed directory are ordered
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BrowseSort {
+    Size,
+    Name,
+    Count,
+    Modified,
+}
+
+impl BrowseSort {
+    fn next(self) -> Self {
+        match self {
+            Self::Size => Self::Name,
+            Self::Name => Self::Count,
+            Self::Count => Self::Modified,
+            Self::Modified => Self::Size,
+        }
+    }
+    fn name(self) -> &'static str {
+        match self {
+            Self::Size => "size",
+            Self::Name => "name",
+            Self::Count => "count",
+            Self::Modified => "mtime",
+        }
+    }
+}
+
+/// What a line of the browser refers to in the current node
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ItemRef {
+    Dir(usize),
+    File(usize),
+}
+
+struct Item {
+    target: ItemRef,
+    name: String,
+    size: u64,
+    count: u64,
+    modified: Option<SystemTime>,
+}
+
+/// List the subdirectories and files of a node, sorted
+fn list_items(
+    node: &DirNode,
+    sort: BrowseSort,
+) -> Vec<Item> {
+    let dirs = node.children.iter().enumerate().map(|(i, d)| Item {
+        target: ItemRef::Dir(i),
+        name: format!("{}/", d.name.to_string_lossy()),
+        size: d.size,
+        count: d.file_count,
+        modified: d.modified,
+    });
+    let files = node.files.iter().enumerate().map(|(i, f)| Item {
+        target: ItemRef::File(i),
+        name: f.name.to_string_lossy().to_string(),
+        size: f.size,
+        count: 1,
+        modified: f.modified,
+    });
+    let mut items: Vec<Item> = dirs.chain(files).collect();
+    match sort {
+        BrowseSort::Size => items.sort_by_key(|i| Reverse(i.size)),
+        BrowseSort::Name => items.sort_by(|a, b| a.name.cmp(&b.name)),
+        BrowseSort::Count => items.sort_by_key(|i| Reverse(i.count)),
+        BrowseSort::Modified => items.sort_by_key(|i| Reverse(i.modified)),
+    }
+    items
+}
+
+fn shift(
+    value: u64,
+    delta: i64,
+) -> u64 {
+    (value as i64 + delta).max(0) as u64
+}
+
+/// Add the deltas to the root and to each node along the `stack` of indices
+fn adjust_ancestors(
+    root: &mut DirNode,
+    stack: &[usize],
+    size_delta: i64,
+    files_delta: i64,
+) {
+    let mut node = root;
+    node.size = shift(node.size, size_delta);
+    node.file_count = shift(node.file_count, files_delta);
+    for &i in stack {
+        node = &mut node.children[i];
+        node.size = shift(node.size, size_delta);
+        node.file_count = shift(node.file_count, files_delta);
+    }
+}
+
+fn node_at<'n>(
+    root: &'n DirNode,
+    stack: &[usize],
+) -> &'n DirNode {
+    stack.iter().fold(root, |node, &i| &node.children[i])
+}
+
+fn node_at_mut<'n>(
+    root: &'n mut DirNode,
+    stack: &[usize],
+) -> &'n mut DirNode {
+    stack.iter().fold(root, |node, &i| &mut node.children[i])
+}
+
+/// Holds the writer the terminal was set up on, and restores the
+/// terminal through it when dropped, even on error
+struct TerminalGuard<'w, W: Write> {
+    w: &'w mut W,
+}
+
+impl<'w, W: Write> TerminalGuard<'w, W> {
+    fn new(w: &'w mut W) -> io::Result<Self> {
+        terminal::enable_raw_mode()?;
+        if let Err(e) = execute!(w, EnterAlternateScreen, cursor::Hide) {
+            let _ = terminal::disable_raw_mode();
+            return Err(e);
+        }
+        Ok(Self { w })
+    }
+}
+
+impl<W: Write> Drop for TerminalGuard<'_, W> {
+    fn drop(&mut self) {
+        let _ = execute!(self.w, cursor::Show, LeaveAlternateScreen);
+        let _ = terminal::disable_raw_mode();
+    }
+}
+
+/// A full-screen navigable view over a completed scan
+pub struct Browser<'r> {
+    base_path: PathBuf,
+    root: DirNode,
+    /// indices of the directories entered, from the root
+    stack: Vec<usize>,
+    selection: usize,
+    scroll: usize,
+    sort: BrowseSort,
+    status: String,
+    ascii: bool,
+    rescan: &'r dyn Fn(&Path) -> io::Result<DirNode>,
+}
+
+impl<'r> Browser<'r> {
+    /// Create a browser over `root`, which must have been scanned with its files.
+    ///
+    /// `rescan` is called to rebuild the node of a directory on user request
+    pub fn new(
+        base_path: &Path,
+        root: DirNode,
+        ascii: bool,
+        rescan: &'r dyn Fn(&Path) -> io::Result<DirNode>,
+    ) -> Self {
+        Self {
+            base_path: base_path.to_path_buf(),
+            root,
+            stack: Vec::new(),
+            selection: 0,
+            scroll: 0,
+            sort: BrowseSort::Size,
+            status: String::new(),
+            ascii,
+            rescan,
+        }
+    }
+
+    pub fn run<W: Write>(
+        mut self,
+        w: &mut W,
+    ) -> io::Result<()> {
+        let guard = TerminalGuard::new(w)?;
+        loop {
+            self.draw(guard.w)?;
+            let Event::Key(key) = event::read()? else {
+                continue;
+            };
+            if key.kind != KeyEventKind::Press {
+                continue;
+            }
+            self.status.clear();
+            if !self.handle_key(guard.w, key)? {
+                return Ok(());
+            }
+        }
+    }
+
+    fn current(&self) -> &DirNode {
+        node_at(&self.root, &self.stack)
+    }
+
+    fn path_of(
+        &self,
+        stack: &[usize],
+    ) -> PathBuf {
+        let mut path = self.base_path.clone();
+        let mut node = &self.root;
+        for &i in stack {
+            node = &node.children[i];
+            path.push(&node.name);
+        }
+        path
+    }
+
+    fn selected(&self) -> Option<ItemRef> {
+        list_items(self.current(), self.sort)
+            .get(self.selection)
+            .map(|item| item.target)
+    }
+
+    /// Handle a key, return false when the browser must be closed
+    fn handle_key<W: Write>(
+        &mut self,
+        w: &mut W,
+        key: KeyEvent,
+    ) -> io::Result<bool> {
+        let len = list_items(self.current(), self.sort).len();
+        let page = terminal::size()?.1.saturating_sub(3) as usize;
+        match key.code {
+            KeyCode::Char('q') | KeyCode::Esc => return Ok(false),
+            KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                return Ok(false);
+            }
+            KeyCode::Up | KeyCode::Char('k') => {
+                self.selection = self.selection.saturating_sub(1);
+            }
+            KeyCode::Down | KeyCode::Char('j') => {
+                self.selection = (self.selection + 1).min(len.saturating_sub(1));
+            }
+            KeyCode::PageUp => {
+                self.selection = self.selection.saturating_sub(page);
+            }
+            KeyCode::PageDown => {
+                self.selection = (self.selection + page).min(len.saturating_sub(1));
+            }
+            KeyCode::Home => self.selection = 0,
+            KeyCode::End => self.selection = len.saturating_sub(1),
+            KeyCode::Enter | KeyCode::Right | KeyCode::Char('l') => {
+                if let Some(ItemRef::Dir(i)) = self.selected() {
+                    self.stack.push(i);
+                    self.selection = 0;
+                    self.scroll = 0;
+                }
+            }
+            KeyCode::Left | KeyCode::Backspace | KeyCode::Char('h') => {
+                if let Some(i) = self.stack.pop() {
+                    self.selection = list_items(self.current(), self.sort)
+                        .iter()
+                        .position(|item| item.target == ItemRef::Dir(i))
+                        .unwrap_or(0);
+                }
+            }
+            KeyCode::Char('s') => {
+                self.sort = self.sort.next();
+                self.selection = 0;
+                self.scroll = 0;
+            }
+            KeyCode::Char('r') => {
+                let mut target = self.stack.clone();
+                if let Some(ItemRef::Dir(i)) = self.selected() {
+                    target.push(i);
+                }
+                self.rescan_at(w, &target)?;
+            }
+            KeyCode::Char('d') => {
+                self.delete_selected(w)?;
+            }
+            _ => {}
+        }
+        Ok(true)
+    }
+
+    fn rescan_at<W: Write>(
+        &mut self,
+        w: &mut W,
+        target: &[usize],
+    ) -> io::Result<()> {
+        let path = self.path_of(target);
+        self.status = format!("Scanning {}...", path.display());
+        self.draw(w)?;
+        match (self.rescan)(&path) {
+            Ok(mut node) => {
+                let old = node_at_mut(&mut self.root, target);
+                node.name = old.name.clone();
+                let size_delta = node.size as i64 - old.size as i64;
+                let files_delta = node.file_count as i64 - old.file_count as i64;
+                *old = node;
+                if let Some((_, parents)) = target.split_last() {
+                    adjust_ancestors(&mut self.root, parents, size_delta, files_delta);
+                }
+                self.status = format!("Rescanned {}", path.display());
+            }
+            Err(e) => {
+                self.status = format!("Error scanning {}: {}", path.display(), e);
+            }
+        }
+        let len = list_items(self.current(), self.sort).len();
+        self.selection = self.selection.min(len.saturating_sub(1));
+        Ok(())
+    }
+
+    fn delete_selected<W: Write>(
+        &mut self,
+        w: &mut W,
+    ) -> io::Result<()> {
+        let Some(target) = self.selected() else {
+            return Ok(());
+        };
+        let current = self.current();
+        let (name, is_dir) = match target {
+            ItemRef::Dir(i) => (&current.children[i].name, true),
+            ItemRef::File(i) => (&current.files[i].name, false),
+        };
+        let path = self.path_of(&self.stack).join(name);
+        self.status = format!("Delete {}? [y/N]", path.display());
+        self.draw(w)?;
+        let confirmed = loop {
+            if let Event::Key(key) = event::read()? {
+                if key.kind == KeyEventKind::Press {
+                    break matches!(key.code, KeyCode::Char('y') | KeyCode::Char('Y'));
+                }
+            }
+        };
+        if !confirmed {
+            self.status = "Deletion cancelled".to_string();
+            return Ok(());
+        }
+        let res = if is_dir {
+            fs::remove_dir_all(&path)
+        } else {
+            fs::remove_file(&path)
+        };
+        if let Err(e) = res {
+            self.status = format!("Error deleting {}: {}", path.display(), e);
+            return Ok(());
+        }
+        let current = node_at_mut(&mut self.root, &self.stack);
+        let (size, count) = match target {
+            ItemRef::Dir(i) => {
+                let dir = current.children.remove(i);
+                (dir.size, dir.file_count)
+            }
+            ItemRef::File(i) => {
+                let file = current.files.remove(i);
+                current.own_size = current.own_size.saturating_sub(file.size);
+                (file.size, 1)
+            }
+        };
+        let stack = self.stack.clone();
+        adjust_ancestors(&mut self.root, &stack, -(size as i64), -(count as i64));
+        self.status = format!("Deleted {}", path.display());
+        let len = list_items(self.current(), self.sort).len();
+        self.selection = self.selection.min(len.saturating_sub(1));
+        Ok(())
+    }
+
+    fn draw<W: Write>(
+        &mut self,
+        w: &mut W,
+    ) -> io::Result<()> {
+        let (width, height) = terminal::size()?;
+        let width = width as usize;
+        let list_height = (height as usize).saturating_sub(2).max(1);
+        if self.selection < self.scroll {
+            self.scroll = self.selection;
+        } else if self.selection >= self.scroll + list_height {
+            self.scroll = self.selection + 1 - list_height;
+        }
+        let current = self.current();
+        let items = list_items(current, self.sort);
+        let now = SystemTime::now();
+        queue!(w, terminal::Clear(ClearType::All), cursor::MoveTo(0, 0))?;
+        let header = format!(
+            " {}   total: {}   files: {}   sort: {}",
+            self.path_of(&self.stack).display(),
+            format_bytes(current.size),
+            current.file_count,
+            self.sort.name(),
+        );
+        queue!(
+            w,
+            SetAttribute(Attribute::Reverse),
+            Print(fit(&header, width)),
+            SetAttribute(Attribute::Reset),
+        )?;
+        let max_size = items.iter().map(|i| i.size).max().unwrap_or(0).max(1);
+        for (row, item) in items.iter().enumerate().skip(self.scroll).take(list_height) {
+            let share = item.size as f32 / max_size as f32;
+            let bar = if self.ascii {
+                let count = (share * BAR_WIDTH as f32).round() as usize;
+                format!("{}{}", "#".repeat(count), " ".repeat(BAR_WIDTH - count))
+            } else {
+                format!("{:<width$}", ProgressBar::new(share, BAR_WIDTH), width = BAR_WIDTH)
+            };
+            let age = item
+                .modified
+                .map(|t| Age(now.duration_since(t).unwrap_or_default()).to_string())
+                .unwrap_or_default();
+            let line = format!(
+                " {} {:>7} {:>8} {:>5}  {}",
+                bar,
+                format_bytes(item.size),
+                item.count,
+                age,
+                item.name,
+            );
+            queue!(w, cursor::MoveTo(0, (row - self.scroll + 1) as u16))?;
+            if row == self.selection {
+                queue!(
+                    w,
+                    SetAttribute(Attribute::Reverse),
+                    Print(fit(&line, width)),
+                    SetAttribute(Attribute::Reset),
+                )?;
+            } else {
+                queue!(w, Print(fit(&line, width)))?;
+            }
+        }
+        if items.is_empty() {
+            queue!(w, cursor::MoveTo(0, 1), Print(" (empty directory)"))?;
+        }
+        let footer = if self.status.is_empty() {
+            " ↑↓ move  → enter  ← back  s sort  r rescan  d delete  q quit"
+        } else {
+            &self.status
+        };
+        queue!(
+            w,
+            cursor::MoveTo(0, height.saturating_sub(1)),
+            SetAttribute(Attribute::Reverse),
+            Print(fit(footer, width)),
+            SetAttribute(Attribute::Reset),
+        )?;
+        w.flush()
+    }
+}
+
+/// Truncate or pad a line to exactly `width` chars
+fn fit(
+    s: &str,
+    width: usize,
+) -> String {
+    let mut line: String = s.chars().take(width).collect();
+    let len = line.chars().count();
+    line.extend(std::iter::repeat(' ').take(width - len));
+    line
+}
+
+#[test]
+fn test_adjust_ancestors() {
+    let leaf = |name: &str, size: u64| DirNode {
+        size,
+        file_count: 1,
+        ..DirNode::new(name.into())
+    };
+    let mut root = DirNode {
+        size: 30,
+        file_count: 3,
+        children: vec![
+            leaf("a", 10),
+            DirNode {
+                size: 20,
+                file_count: 2,
+                children: vec![leaf("c", 20)],
+                ..DirNode::new("b".into())
+            },
+        ],
+        ..DirNode::new("/r".into())
+    };
+    // deleting /r/b/c
+    adjust_ancestors(&mut root, &[1], -20, -1);
+    assert_eq!((root.size, root.file_count), (10, 2));
+    assert_eq!(node_at(&root, &[1]).size, 0);
+    assert_eq!(node_at(&root, &[0]).size, 10);
+    let items = list_items(&root, BrowseSort::Size);
+    assert_eq!(items[0].name, "a/");
+    assert_eq!(items[1].target, ItemRef::Dir(1));
+}
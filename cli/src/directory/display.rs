@@ -1,12 +1,31 @@
 use {
     crate::{
         Args,
-        directory::DirectoryScanner,
+        age::Age,
+        args::{FilesMode, ProgressMode},
+        directory::{
+            DirNode,
+            DirectoryScanner,
+            ScanIndex,
+            ScanResult,
+            ages::write_ages,
+            artifacts::{artifacts_to_json, write_reclaimable},
+            browser::Browser,
+            duplicates::{duplicates_to_json, write_duplicates},
+            errors::write_scan_errors,
+            ncdu::{export_ncdu, import_ncdu},
+            output::{scan_to_json, write_scan_csv},
+            owners::write_owners,
+            progress::used_inodes,
+            tree::write_tree,
+            types::write_types,
+        },
         process::graph::render_bar_compact,
     },
     std::{
-        io::{self, Write},
-        path::PathBuf,
+        io::{self, IsTerminal, Write},
+        path::{Path, PathBuf},
+        time::SystemTime,
     },
     termimad::{
         MadSkin,
@@ -22,6 +41,167 @@ pub fn display_directories<W: Write>(
     w: &mut W,
     args: &Args,
 ) -> io::Result<()> {
+    let scanned = match &args.import_ncdu {
+        Some(file) => import_scan(file, args),
+        None => scan_directories(args)?,
+    };
+    let Some((base_path, mut result)) = scanned else {
+        return Ok(());
+    };
+
+    if let Some(file) = &args.export_ncdu {
+        if let Some(tree) = &result.tree {
+            match export_ncdu(file, tree) {
+                Ok(()) => eprintln!("Scan exported to {}", file.display()),
+                Err(e) => eprintln!("Error exporting the scan to {}: {}", file.display(), e),
+            }
+        }
+    }
+
+    write_result(w, args, &base_path, &mut result)?;
+
+    // the JSON of a scan holds its errors, other outputs get a footer,
+    // on stderr when stdout must stay parseable
+    let errors = &result.errors;
+    let errors_in_json = args.json && !args.interactive && !needs_own_json(args);
+    if args.json || args.csv || args.interactive {
+        if !errors_in_json {
+            write_scan_errors(&mut io::stderr(), errors, args)?;
+        }
+    } else {
+        write_scan_errors(w, errors, args)?;
+    }
+    if args.strict && !errors.is_empty() {
+        return Err(io::Error::new(
+            io::ErrorKind::Other,
+            format!("{} entries couldn't be read (--strict)", errors.total()),
+        ));
+    }
+    Ok(())
+}
+
+/// Write the view selected in arguments
+fn write_result<W: Write>(
+    w: &mut W,
+    args: &Args,
+    base_path: &Path,
+    result: &mut ScanResult,
+) -> io::Result<()> {
+    if args.interactive {
+        let Some(tree) = result.tree.take() else {
+            return Ok(());
+        };
+        let rescan = |path: &Path| {
+            let scanner = DirectoryScanner::new(path)
+                .recursive()
+                .build_tree(None)
+                .tree_files(true)
+                .show_all(args.all)
+                .timeout(args.timeout.and_then(|t| t.as_duration()))
+                .older_than(args.older_than.map(Age::as_duration))
+                .newer_than(args.newer_than.map(Age::as_duration));
+            scanner.scan().map(|r| r.tree.unwrap_or_else(|| DirNode::new(path.into())))
+        };
+        return Browser::new(base_path, tree, args.ascii, &rescan).run(w);
+    }
+
+    if let Some(artifacts) = &result.artifacts {
+        if args.json {
+            let value = artifacts_to_json(artifacts, args.limit.or_max());
+            return writeln!(w, "{}", serde_json::to_string_pretty(&value).unwrap());
+        }
+        return write_reclaimable(w, artifacts, args.limit.or_max(), args);
+    }
+
+    if let Some(owners) = &result.owners {
+        let limit = args.limit.or_max();
+        let dirs_limit = args.owner_dirs.unwrap_or(0);
+        if args.json {
+            let value = owners.to_json(limit, dirs_limit);
+            return writeln!(w, "{}", serde_json::to_string_pretty(&value).unwrap());
+        }
+        return write_owners(w, owners, limit, dirs_limit, args);
+    }
+
+    if let Some(types) = &result.types {
+        if args.json {
+            return writeln!(w, "{}", serde_json::to_string_pretty(&types.to_json()).unwrap());
+        }
+        return write_types(w, types, args.limit.or_max(), args);
+    }
+
+    if let Some(mut tree) = result.tree.take().filter(|_| args.tree) {
+        if !args.recursive {
+            // imported and exported trees are complete
+            tree.prune(args.depth);
+        }
+        tree.sort_by_size();
+        if args.json {
+            return writeln!(w, "{}", serde_json::to_string_pretty(&tree.to_json()).unwrap());
+        }
+        return write_tree(w, &tree, args.min_percent, args);
+    }
+
+    if let Some(candidates) = result.duplicates.take() {
+        eprintln!("Comparing file contents...");
+        let sets = candidates.find_duplicates();
+        if args.json {
+            return writeln!(w, "{}", serde_json::to_string_pretty(&duplicates_to_json(&sets)).unwrap());
+        }
+        return write_duplicates(w, &sets, args.limit.apply(sets.len()), args);
+    }
+
+    if let Some(ages) = &result.ages {
+        if args.json {
+            return writeln!(w, "{}", serde_json::to_string_pretty(&ages.to_json()).unwrap());
+        }
+        return write_ages(w, ages, args);
+    }
+
+    let show_dirs = args.files != Some(FilesMode::Only);
+    let show_files = args.files.is_some();
+
+    if args.json {
+        let limit = args.limit.or_max();
+        let value = scan_to_json(base_path, result, limit, args.min_size.unwrap_or(0));
+        return writeln!(w, "{}", serde_json::to_string_pretty(&value).unwrap());
+    }
+    if args.csv {
+        let limit = args.limit.or_max();
+        return write_scan_csv(w, result, limit, show_dirs, show_files, args);
+    }
+
+    if show_dirs {
+        if result.directories.is_empty() {
+            writeln!(w, "\nNo subdirectories found.")?;
+        } else {
+            let rows: Vec<SizedRow> = result
+                .directories
+                .iter()
+                .map(|e| SizedRow::new(e.size, &e.path, e.modified))
+                .collect();
+            write_sized_table(w, "Directories", &rows, args)?;
+        }
+    }
+
+    if show_files {
+        if result.files.is_empty() {
+            writeln!(w, "\nNo files found.")?;
+        } else {
+            let rows: Vec<SizedRow> = result
+                .files
+                .iter()
+                .map(|e| SizedRow::new(e.size, &e.path, e.modified))
+                .collect();
+            write_sized_table(w, "Files", &rows, args)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Scan the directory given in arguments, return the scanned path and what was found
+fn scan_directories(args: &Args) -> io::Result<Option<(PathBuf, ScanResult)>> {
     let base_path = if args.root {
         PathBuf::from("/")
     } else if let Some(ref path) = args.path {
@@ -30,16 +210,24 @@ pub fn display_directories<W: Write>(
         std::env::current_dir()?
     };
 
-    writeln!(w, "Scanning directory: {}", base_path.display())?;
-    if !args.all {
-        writeln!(w, "(Skipping hidden and build directories - use --all to show everything)")?;
+    // progress messages go to stderr so that JSON and CSV outputs stay parseable
+    eprintln!("Scanning directory: {}", base_path.display());
+    if !args.all && !args.reclaimable {
+        eprintln!("(Skipping hidden and build directories - use --all to show everything)");
     }
-    writeln!(w, "Please wait...")?;
-    w.flush()?;
+    eprintln!("Please wait...");
 
     let mut scanner = DirectoryScanner::new(&base_path);
     
-    if args.recursive {
+    if args.interactive || args.export_ncdu.is_some() {
+        // the browser and ncdu dumps hold the whole hierarchy, files included
+        scanner = scanner.recursive().build_tree(None).tree_files(true);
+    } else if args.tree {
+        // sizes in the tree are inclusive, so the whole hierarchy must be walked
+        let tree_depth = (!args.recursive).then_some(args.depth);
+        scanner = scanner.recursive().build_tree(tree_depth);
+    } else if args.recursive || args.reclaimable || args.by_owner {
+        // artifacts and owned files can be anywhere below the scanned directory
         scanner = scanner.recursive();
     } else {
         scanner = scanner.max_depth(args.depth);
@@ -47,60 +235,203 @@ pub fn display_directories<W: Write>(
 
     scanner = scanner.show_all(args.all);
 
-    let entries = match scanner.scan() {
-        Ok(e) => e,
+    scanner = scanner
+        .timeout(args.timeout.and_then(|t| t.as_duration()))
+        .max_results(args.limit.or_max())
+        .min_size(args.min_size.unwrap_or(0));
+    if args.files.is_some() {
+        scanner = scanner.max_files(args.limit.or_max());
+    }
+
+    scanner = scanner
+        .track_types(args.types)
+        .track_ages(args.ages)
+        .track_duplicates(args.duplicates)
+        .track_artifacts(args.reclaimable)
+        .track_owners(args.by_owner)
+        .owner_dirs_depth(args.owner_dirs.map(|_| args.depth))
+        .older_than(args.older_than.map(Age::as_duration))
+        .newer_than(args.newer_than.map(Age::as_duration));
+
+    let show_progress = match args.progress {
+        ProgressMode::Auto => io::stderr().is_terminal(),
+        ProgressMode::Always => true,
+        ProgressMode::Never => false,
+    };
+    if show_progress {
+        scanner = scanner
+            .progress(true)
+            .expected_entries(used_inodes(&base_path, args));
+    }
+
+    let mut index_file = None;
+    if args.cache {
+        if needs_each_file(args) {
+            eprintln!("(The scan index only holds directory totals, --cache is ignored with file level options)");
+        } else if let Some(file) = ScanIndex::default_file(&base_path, args.all) {
+            let index = match ScanIndex::load(&file, &base_path) {
+                Ok(index) => index,
+                Err(e) => {
+                    if e.kind() != io::ErrorKind::NotFound {
+                        eprintln!("Ignoring the scan index {}: {}", file.display(), e);
+                    }
+                    ScanIndex::new(&base_path)
+                }
+            };
+            scanner = scanner.index(Some(index));
+            index_file = Some(file);
+        } else {
+            eprintln!("(No cache directory found, --cache is ignored)");
+        }
+    }
+
+    let mut result = match scanner.scan() {
+        Ok(r) => r,
         Err(err) => {
             eprintln!("\nError scanning directory: {}", err);
-            return Ok(());
+            return Ok(None);
         }
     };
 
-    if entries.is_empty() {
-        writeln!(w, "\nNo subdirectories found.")?;
-        return Ok(());
+    match (index_file, result.index.take()) {
+        (Some(file), Some(index)) => {
+            eprintln!(
+                "{} of {} directories unchanged since the previous scan",
+                result.reused_dirs,
+                index.len(),
+            );
+            if let Err(e) = index.save(&file) {
+                eprintln!("Error saving the scan index {}: {}", file.display(), e);
+            }
+        }
+        (Some(_), None) => {
+            eprintln!(
+                "{} directories unchanged since the previous scan (the index is only saved by recursive scans)",
+                result.reused_dirs,
+            );
+        }
+        _ => {}
     }
 
-    let limit = match args.limit.as_str() {
-        "all" => entries.len(),
-        "20" => 20.min(entries.len()),
-        _ => 10.min(entries.len()),
+    Ok(Some((base_path, result)))
+}
+
+/// Read a dump of ncdu, return its root and the scan it holds
+fn import_scan(file: &Path, args: &Args) -> Option<(PathBuf, ScanResult)> {
+    if args.ages || args.duplicates || args.reclaimable || args.by_owner {
+        eprintln!("--ages, --duplicates, --reclaimable and --by-owner need a scan, they can't be used with an import");
+        return None;
+    }
+    eprintln!("Importing ncdu dump: {}", file.display());
+    let tree = match import_ncdu(file) {
+        Ok(tree) => tree,
+        Err(e) => {
+            eprintln!("\nError importing {}: {}", file.display(), e);
+            return None;
+        }
     };
+    let base_path = PathBuf::from(&tree.name);
+    let max_depth = (!args.recursive).then_some(args.depth);
+    let max_files = if args.files.is_some() { args.limit.or_max() } else { 0 };
+    let result = ScanResult::from_tree(
+        tree,
+        max_depth,
+        args.limit.or_max(),
+        max_files,
+        args.min_size.unwrap_or(0),
+        args.types,
+    );
+    Some((base_path, result))
+}
 
-    let display_entries = &entries[..limit];
-    let max_size = entries.first().map(|e| e.size).unwrap_or(1);
+/// Whether the output is one of the JSON documents which don't
+/// hold the scan errors: types, tree, duplicates, ages, artifacts and owners
+fn needs_own_json(args: &Args) -> bool {
+    args.types || args.tree || args.duplicates || args.ages || args.reclaimable || args.by_owner
+}
+
+/// Whether the requested output needs a visit of every file, which
+/// the scan index can't provide
+fn needs_each_file(args: &Args) -> bool {
+    args.files.is_some()
+        || args.types
+        || args.ages
+        || args.duplicates
+        || args.reclaimable
+        || args.by_owner
+        || args.interactive
+        || args.export_ncdu.is_some()
+        || args.older_than.is_some()
+        || args.newer_than.is_some()
+}
+
+/// A row of a "Top N ... by Size" table
+struct SizedRow {
+    size: u64,
+    path: String,
+    modified: Option<SystemTime>,
+}
+
+impl SizedRow {
+    fn new(size: u64, path: &Path, modified: Option<SystemTime>) -> Self {
+        Self {
+            size,
+            path: path.display().to_string(),
+            modified,
+        }
+    }
+}
+
+/// Write a "Top N ... by Size" table, biggest rows first
+fn write_sized_table<W: Write>(
+    w: &mut W,
+    what: &str,
+    rows: &[SizedRow],
+    args: &Args,
+) -> io::Result<()> {
+    let limit = args.limit.apply(rows.len());
+
+    let display_rows = &rows[..limit];
+    let max_size = rows.first().map(|r| r.size).unwrap_or(1);
+    let now = SystemTime::now();
 
     let mut expander = OwningTemplateExpander::new();
     expander.set_default("");
 
-    for entry in display_entries {
-        let size_pct = (entry.size as f32 / max_size as f32) * 100.0;
-        let size_display = format_bytes(entry.size);
-        let path_display = entry.path.display().to_string();
+    for row in display_rows {
+        let size_pct = (row.size as f32 / max_size as f32) * 100.0;
+        let modified = row
+            .modified
+            .map(|t| Age(now.duration_since(t).unwrap_or_default()).to_string());
 
         expander
             .sub("rows")
             .set_md("bar", render_bar_compact(size_pct, 10, args.ascii))
-            .set("size", size_display)
-            .set("path", path_display);
+            .set("size", format_bytes(row.size))
+            .set_option("modified", modified)
+            .set("path", &row.path);
     }
 
     let mut tbl = TableBuilder::default();
     tbl.col(Col::new("Usage", "${bar}").align_content(Alignment::Left))
         .col(Col::new("Size", "${size}").align_content(Alignment::Right))
+        .col(Col::new("Modified", "${modified}").align_content(Alignment::Right))
         .col(Col::new("Path", "${path}").align_content(Alignment::Left));
 
+    writeln!(w, "\nTop {} {} by Size:\n", limit, what)?;
+    make_skin(args).write_owning_expander_md(w, &expander, &tbl)
+}
+
+pub(crate) fn make_skin(args: &Args) -> MadSkin {
     let mut skin = if args.color() {
         make_colored_skin()
     } else {
         MadSkin::no_style()
     };
-
     if args.ascii {
         skin.limit_to_ascii();
     }
-
-    writeln!(w, "\nTop {} Directories by Size:\n", limit)?;
-    skin.write_owning_expander_md(w, &expander, &tbl)
+    skin
 }
 
 fn make_colored_skin() -> MadSkin {
@@ -110,7 +441,7 @@ fn make_colored_skin() -> MadSkin {
     }
 }
 
-fn format_bytes(bytes: u64) -> String {
+pub(crate) fn format_bytes(bytes: u64) -> String {
     if bytes < 1024 {
         format!("{}B", bytes)
     } else if bytes < 1024 * 1024 {
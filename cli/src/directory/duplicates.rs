@@ -0,0 +1,303 @@
+use {
+    crate::{
+        Args,
+        directory::{
+            display::{format_bytes, make_skin},
+            output::path_value,
+        },
+    },
+    serde_json::{Value, json},
+    std::{
+        collections::{HashMap, HashSet, hash_map::DefaultHasher},
+        fs::{File, Metadata},
+        hash::Hasher,
+        io::{self, Read, Seek, SeekFrom, Write},
+        path::PathBuf,
+    },
+    termimad::minimad::{OwningTemplateExpander, TableBuilder, Col, Alignment},
+};
+
+/// Size of the chunks read at the start and end of a file for the partial hash
+static PARTIAL_HASH_LEN: u64 = 4096;
+
+/// Files gathered during a scan, grouped by size, which may be duplicates
+#[derive(Debug, Clone, Default)]
+pub struct DuplicateCandidates {
+    by_size: HashMap<u64, Vec<PathBuf>>,
+    /// (device, inode) of the already seen files having several links
+    linked: HashSet<(u64, u64)>,
+}
+
+/// A set of files with identical content
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DuplicateSet {
+    /// size of each file
+    pub size: u64,
+    pub paths: Vec<PathBuf>,
+}
+
+impl DuplicateSet {
+    /// bytes which would be freed by keeping only one copy
+    pub fn wasted(&self) -> u64 {
+        self.size * (self.paths.len() as u64 - 1)
+    }
+}
+
+impl DuplicateCandidates {
+    pub fn add(&mut self, path: PathBuf, metadata: &Metadata) {
+        let size = metadata.len();
+        if size == 0 {
+            return; // empty files aren't interesting duplicates
+        }
+        if let Some(inode) = hard_linked_inode(metadata) {
+            if !self.linked.insert(inode) {
+                return; // another link to an already known file
+            }
+        }
+        self.by_size.entry(size).or_default().push(path);
+    }
+    /// Compare the contents of same-size files, first with a hash of
+    /// their first and last bytes, then with a hash of their whole content,
+    /// then byte for byte among the files sharing this hash, so that files
+    /// are never reported as duplicates on a hash collision.
+    ///
+    /// Return the sets of duplicates, the most wasteful first
+    pub fn find_duplicates(self) -> Vec<DuplicateSet> {
+        let mut sets = Vec::new();
+        for (size, paths) in self.by_size {
+            if paths.len() < 2 {
+                continue;
+            }
+            for partial in split_by_hash(paths, |p| partial_hash(p, size)) {
+                for hashed in split_by_hash(partial, full_hash) {
+                    for same in split_by_content(hashed) {
+                        sets.push(DuplicateSet { size, paths: same });
+                    }
+                }
+            }
+        }
+        sets.sort_by(|a, b| {
+            b.wasted()
+                .cmp(&a.wasted())
+                .then_with(|| a.paths.cmp(&b.paths))
+        });
+        sets
+    }
+}
+
+/// Return the (device, inode) pair of a file with several hard links
+#[cfg(unix)]
+fn hard_linked_inode(metadata: &Metadata) -> Option<(u64, u64)> {
+    use std::os::unix::fs::MetadataExt;
+    (metadata.nlink() > 1).then(|| (metadata.dev(), metadata.ino()))
+}
+
+#[cfg(not(unix))]
+fn hard_linked_inode(_metadata: &Metadata) -> Option<(u64, u64)> {
+    None
+}
+
+/// Group paths by the hash computed by `f`, returning only the groups of
+/// at least two paths. Unreadable files are dropped.
+fn split_by_hash<F>(paths: Vec<PathBuf>, f: F) -> Vec<Vec<PathBuf>>
+where
+    F: Fn(&PathBuf) -> io::Result<u64>,
+{
+    let mut groups: HashMap<u64, Vec<PathBuf>> = HashMap::new();
+    for path in paths {
+        if let Ok(hash) = f(&path) {
+            groups.entry(hash).or_default().push(path);
+        }
+    }
+    let mut groups: Vec<Vec<PathBuf>> = groups.into_values().filter(|g| g.len() > 1).collect();
+    for group in &mut groups {
+        group.sort();
+    }
+    groups
+}
+
+/// Hash the first and last bytes of a file
+fn partial_hash(path: &PathBuf, size: u64) -> io::Result<u64> {
+    let mut file = File::open(path)?;
+    let mut hasher = DefaultHasher::new();
+    let mut buf = vec![0; PARTIAL_HASH_LEN as usize];
+    let n = read_up_to(&mut file, &mut buf)?;
+    hasher.write(&buf[..n]);
+    if size > 2 * PARTIAL_HASH_LEN {
+        file.seek(SeekFrom::End(-(PARTIAL_HASH_LEN as i64)))?;
+        let n = read_up_to(&mut file, &mut buf)?;
+        hasher.write(&buf[..n]);
+    }
+    Ok(hasher.finish())
+}
+
+/// Hash the whole content of a file, reading it once
+fn full_hash(path: &PathBuf) -> io::Result<u64> {
+    let mut file = File::open(path)?;
+    let mut hasher = DefaultHasher::new();
+    let mut buf = vec![0; 64 * 1024];
+    loop {
+        let n = read_up_to(&mut file, &mut buf)?;
+        if n == 0 {
+            return Ok(hasher.finish());
+        }
+        hasher.write(&buf[..n]);
+    }
+}
+
+/// Group paths by identical content, returning only the groups of at
+/// least two paths. Unreadable files are dropped.
+///
+/// The paths are expected to share a full hash, so there's nearly
+/// always a single group and each file is compared once.
+fn split_by_content(paths: Vec<PathBuf>) -> Vec<Vec<PathBuf>> {
+    let mut groups: Vec<Vec<PathBuf>> = Vec::new();
+    'paths: for path in paths {
+        for group in &mut groups {
+            match same_content(&group[0], &path) {
+                Ok(true) => {
+                    group.push(path);
+                    continue 'paths;
+                }
+                Ok(false) => {}
+                Err(_) => continue 'paths,
+            }
+        }
+        if File::open(&path).is_ok() {
+            groups.push(vec![path]);
+        }
+    }
+    groups.retain(|g| g.len() > 1);
+    groups
+}
+
+/// Compare the whole contents of two files of the same size
+fn same_content(a: &PathBuf, b: &PathBuf) -> io::Result<bool> {
+    let mut file_a = File::open(a)?;
+    let mut file_b = File::open(b)?;
+    let mut buf_a = vec![0; 64 * 1024];
+    let mut buf_b = vec![0; 64 * 1024];
+    loop {
+        let n = read_up_to(&mut file_a, &mut buf_a)?;
+        let m = read_up_to(&mut file_b, &mut buf_b)?;
+        if n != m || buf_a[..n] != buf_b[..m] {
+            return Ok(false);
+        }
+        if n == 0 {
+            return Ok(true);
+        }
+    }
+}
+
+fn read_up_to(file: &mut File, buf: &mut [u8]) -> io::Result<usize> {
+    let mut filled = 0;
+    while filled < buf.len() {
+        let n = file.read(&mut buf[filled..])?;
+        if n == 0 {
+            break;
+        }
+        filled += n;
+    }
+    Ok(filled)
+}
+
+pub fn duplicates_to_json(sets: &[DuplicateSet]) -> Value {
+    Value::Array(
+        sets.iter()
+            .map(|set| {
+                json!({
+                    "size": set.size,
+                    "wasted": set.wasted(),
+                    "paths": set.paths.iter().map(|p| path_value(p)).collect::<Vec<_>>(),
+                })
+            })
+            .collect(),
+    )
+}
+
+/// Write the duplicate sets, one line per file
+pub fn write_duplicates<W: Write>(
+    w: &mut W,
+    sets: &[DuplicateSet],
+    limit: usize,
+    args: &Args,
+) -> io::Result<()> {
+    if sets.is_empty() {
+        return writeln!(w, "\nNo duplicate files found.");
+    }
+    let total_wasted: u64 = sets.iter().map(|s| s.wasted()).sum();
+    let mut expander = OwningTemplateExpander::new();
+    expander.set_default("");
+    for set in sets.iter().take(limit) {
+        for (i, path) in set.paths.iter().enumerate() {
+            let row = expander.sub("rows");
+            if i == 0 {
+                row.set("wasted", format_bytes(set.wasted()))
+                    .set("size", format_bytes(set.size))
+                    .set("copies", set.paths.len());
+            }
+            row.set("path", path.display());
+        }
+    }
+    let mut tbl = TableBuilder::default();
+    tbl.col(Col::new("Wasted", "${wasted}").align_content(Alignment::Right))
+        .col(Col::new("Size", "${size}").align_content(Alignment::Right))
+        .col(Col::new("Copies", "${copies}").align_content(Alignment::Right))
+        .col(Col::new("Path", "${path}").align_content(Alignment::Left));
+    writeln!(
+        w,
+        "\nTop {} Duplicate Sets by Wasted Space ({} wasted in {} sets):\n",
+        limit.min(sets.len()),
+        format_bytes(total_wasted),
+        sets.len(),
+    )?;
+    make_skin(args).write_owning_expander_md(w, &expander, &tbl)
+}
+
+#[cfg(unix)]
+#[test]
+fn test_find_duplicates() {
+    use std::fs;
+    let dir = std::env::temp_dir().join(format!("provis-dup-test-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let big: Vec<u8> = (0..20_000u32).map(|i| (i % 251) as u8).collect();
+    let mut big_variant = big.clone();
+    big_variant[10_000] ^= 1; // same start and end, different middle
+    fs::write(dir.join("a"), &big).unwrap();
+    fs::write(dir.join("b"), &big).unwrap();
+    fs::write(dir.join("c"), &big_variant).unwrap();
+    fs::hard_link(dir.join("a"), dir.join("a-link")).unwrap();
+    fs::write(dir.join("d"), "small").unwrap();
+    fs::write(dir.join("e"), "small").unwrap();
+    fs::write(dir.join("f"), "other").unwrap();
+    fs::write(dir.join("g"), &big_variant).unwrap();
+    let mut candidates = DuplicateCandidates::default();
+    let mut names: Vec<_> = fs::read_dir(&dir).unwrap().flatten().map(|e| e.path()).collect();
+    names.sort();
+    for path in names {
+        let metadata = fs::metadata(&path).unwrap();
+        candidates.add(path, &metadata);
+    }
+    let sets = candidates.find_duplicates();
+    // files put in one bucket by a hash collision are still told apart
+    assert!(split_by_content(vec![dir.join("a"), dir.join("c")]).is_empty());
+    fs::remove_dir_all(&dir).unwrap();
+    assert_eq!(
+        sets,
+        vec![
+            DuplicateSet {
+                size: 20_000,
+                paths: vec![dir.join("a"), dir.join("b")],
+            },
+            DuplicateSet {
+                size: 20_000,
+                paths: vec![dir.join("c"), dir.join("g")],
+            },
+            DuplicateSet {
+                size: 5,
+                paths: vec![dir.join("d"), dir.join("e")],
+            },
+        ]
+    );
+    assert_eq!(sets[0].wasted(), 20_000);
+}
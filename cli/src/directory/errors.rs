@@ -0,0 +1,218 @@
+use {
+    crate::{
+        Args,
+        directory::output::insert_path,
+    },
+    serde_json::{Map, Value},
+    std::{
+        io::{self, Write},
+        path::{Path, PathBuf},
+    },
+};
+
+// linux errno values, not all of them having a stable `io::ErrorKind`
+static EIO: i32 = 5;
+static ELOOP: i32 = 40;
+
+/// How many offending paths are kept, and displayed, per scan, for the
+/// timeouts and for the other errors
+static MAX_SAMPLES: usize = 10;
+
+/// Why an entry couldn't be read during a scan
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScanErrorKind {
+    PermissionDenied,
+    /// removed between its listing and its reading
+    Vanished,
+    Io,
+    Loop,
+    /// no answer before the `--timeout` delay, eg on a hung network mount
+    TimedOut,
+    Other,
+}
+
+impl ScanErrorKind {
+    pub const ALL: [Self; 6] = [
+        Self::PermissionDenied,
+        Self::Vanished,
+        Self::Io,
+        Self::Loop,
+        Self::TimedOut,
+        Self::Other,
+    ];
+    pub fn of(err: &io::Error) -> Self {
+        match err.kind() {
+            io::ErrorKind::PermissionDenied => Self::PermissionDenied,
+            io::ErrorKind::NotFound => Self::Vanished,
+            io::ErrorKind::TimedOut => Self::TimedOut,
+            _ => match err.raw_os_error() {
+                Some(code) if code == EIO => Self::Io,
+                Some(code) if code == ELOOP => Self::Loop,
+                _ => Self::Other,
+            },
+        }
+    }
+    fn index(self) -> usize {
+        match self {
+            Self::PermissionDenied => 0,
+            Self::Vanished => 1,
+            Self::Io => 2,
+            Self::Loop => 3,
+            Self::TimedOut => 4,
+            Self::Other => 5,
+        }
+    }
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::PermissionDenied => "permission-denied",
+            Self::Vanished => "vanished",
+            Self::Io => "io-error",
+            Self::Loop => "symlink-loop",
+            Self::TimedOut => "timed-out",
+            Self::Other => "other",
+        }
+    }
+}
+
+/// A path which couldn't be read
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScanError {
+    pub path: PathBuf,
+    pub kind: ScanErrorKind,
+    /// whether a whole directory, with its content, was missed
+    pub subtree: bool,
+}
+
+/// The errors met during a scan, counted by kind, with the first offending paths
+#[derive(Debug, Clone, Default)]
+pub struct ScanErrors {
+    counts: [u64; 6],
+    unread_dirs: u64,
+    samples: Vec<ScanError>,
+}
+
+impl ScanErrors {
+    /// Record an error on `path`, `subtree` telling whether it's
+    /// a directory which couldn't be listed
+    pub fn add(&mut self, path: &Path, err: &io::Error, subtree: bool) {
+        let kind = ScanErrorKind::of(err);
+        self.counts[kind.index()] += 1;
+        if subtree {
+            self.unread_dirs += 1;
+        }
+        // timeouts are worth knowing about, so they have their own budget
+        // and aren't hidden by other errors
+        let is_timeout = kind == ScanErrorKind::TimedOut;
+        let sampled = self
+            .samples
+            .iter()
+            .filter(|s| (s.kind == ScanErrorKind::TimedOut) == is_timeout)
+            .count();
+        if sampled < MAX_SAMPLES {
+            self.samples.push(ScanError {
+                path: path.to_path_buf(),
+                kind,
+                subtree,
+            });
+        }
+    }
+    pub fn total(&self) -> u64 {
+        self.counts.iter().sum()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.total() == 0
+    }
+    /// Number of directories whose content wasn't counted
+    pub fn unread_dirs(&self) -> u64 {
+        self.unread_dirs
+    }
+    pub fn count(&self, kind: ScanErrorKind) -> u64 {
+        self.counts[kind.index()]
+    }
+    pub fn samples(&self) -> &[ScanError] {
+        &self.samples
+    }
+    pub fn to_json(&self) -> Value {
+        let mut obj = Map::new();
+        obj.insert("total".into(), self.total().into());
+        obj.insert("unread-dirs".into(), self.unread_dirs.into());
+        for kind in ScanErrorKind::ALL {
+            obj.insert(kind.name().into(), self.count(kind).into());
+        }
+        let samples: Vec<Value> = self
+            .samples
+            .iter()
+            .map(|e| {
+                let mut sample = Map::new();
+                insert_path(&mut sample, "path", &e.path);
+                sample.insert("kind".into(), e.kind.name().into());
+                sample.insert("subtree".into(), e.subtree.into());
+                Value::Object(sample)
+            })
+            .collect();
+        obj.insert("paths".into(), samples.into());
+        Value::Object(obj)
+    }
+}
+
+/// Write a summary of the errors, if any, below the results of the scan
+pub fn write_scan_errors<W: Write>(
+    w: &mut W,
+    errors: &ScanErrors,
+    args: &Args,
+) -> io::Result<()> {
+    if errors.is_empty() {
+        return Ok(());
+    }
+    let warning = if args.ascii { "!" } else { "⚠" };
+    writeln!(
+        w,
+        "\n{} {} entries couldn't be read, {} directories weren't counted:",
+        warning,
+        errors.total(),
+        errors.unread_dirs(),
+    )?;
+    let counts: Vec<String> = ScanErrorKind::ALL
+        .iter()
+        .filter(|&&kind| errors.count(kind) > 0)
+        .map(|&kind| format!("{}: {}", kind.name(), errors.count(kind)))
+        .collect();
+    writeln!(w, "  {}", counts.join(", "))?;
+    for error in errors.samples() {
+        writeln!(w, "  {:<17} {}", error.kind.name(), error.path.display())?;
+    }
+    if errors.total() > errors.samples().len() as u64 {
+        writeln!(w, "  ...")?;
+    }
+    Ok(())
+}
+
+#[test]
+fn test_scan_errors() {
+    let mut errors = ScanErrors::default();
+    let denied = io::Error::from(io::ErrorKind::PermissionDenied);
+    for i in 0..12 {
+        errors.add(Path::new(&format!("/root/{}", i)), &denied, true);
+    }
+    errors.add(Path::new("/tmp/gone"), &io::Error::from(io::ErrorKind::NotFound), false);
+    errors.add(Path::new("/mnt/bad"), &io::Error::from_raw_os_error(EIO), false);
+    errors.add(Path::new("/a/loop"), &io::Error::from_raw_os_error(ELOOP), true);
+    assert_eq!(errors.total(), 15);
+    assert_eq!(errors.unread_dirs(), 13);
+    assert_eq!(errors.count(ScanErrorKind::PermissionDenied), 12);
+    assert_eq!(errors.count(ScanErrorKind::Vanished), 1);
+    assert_eq!(errors.count(ScanErrorKind::Io), 1);
+    assert_eq!(errors.count(ScanErrorKind::Loop), 1);
+    assert_eq!(errors.samples().len(), MAX_SAMPLES);
+    errors.add(Path::new("/mnt/nfs"), &io::Error::from(io::ErrorKind::TimedOut), true);
+    assert_eq!(errors.count(ScanErrorKind::TimedOut), 1);
+    assert_eq!(errors.samples().last().unwrap().path, Path::new("/mnt/nfs"));
+    // a hung mount doesn't flood the samples
+    let timed_out = io::Error::from(io::ErrorKind::TimedOut);
+    for i in 0..1000 {
+        errors.add(Path::new(&format!("/mnt/nfs/{}", i)), &timed_out, true);
+    }
+    assert_eq!(errors.count(ScanErrorKind::TimedOut), 1001);
+    assert_eq!(errors.samples().len(), 2 * MAX_SAMPLES);
+    assert_eq!(errors.to_json()["permission-denied"], 12);
+}
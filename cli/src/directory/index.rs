@@ -0,0 +1,344 @@
+use {
+    std::{
+        collections::HashMap,
+        ffi::{OsStr, OsString},
+        fs::{self, File, Metadata},
+        io::{self, BufRead, BufReader, BufWriter, Write},
+        os::unix::{
+            ffi::{OsStrExt, OsStringExt},
+            fs::MetadataExt,
+        },
+        path::{Path, PathBuf},
+        str::FromStr,
+        time::{Duration, SystemTime, UNIX_EPOCH},
+    },
+};
+
+static HEADER: &str = "provis-index 2";
+
+/// What tells whether the entries of a directory changed since it was indexed:
+/// creating, removing or renaming an entry updates the mtime of the directory
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DirStamp {
+    pub dev: u64,
+    pub ino: u64,
+    pub mtime: i64,
+    pub mtime_nsec: i64,
+}
+
+impl DirStamp {
+    pub fn of(metadata: &Metadata) -> Self {
+        Self {
+            dev: metadata.dev(),
+            ino: metadata.ino(),
+            mtime: metadata.mtime(),
+            mtime_nsec: metadata.mtime_nsec(),
+        }
+    }
+}
+
+/// What a scan learned about a directory
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IndexedDir {
+    pub stamp: DirStamp,
+    /// bytes of the files directly in the directory
+    pub own_size: u64,
+    /// number of files directly in the directory
+    pub own_files: u64,
+    /// most recent modification time of the files directly in the directory
+    pub own_modified: Option<SystemTime>,
+    /// names of the scanned subdirectories
+    pub subdirs: Vec<OsString>,
+}
+
+/// The directories of a scanned tree, saved between runs so that the
+/// next scan of the same root only reads the directories which changed.
+///
+/// Every directory is still stat'ed, because the mtime of a directory doesn't
+/// change when something deeper in its subtree does, so no subtree total can
+/// be trusted without checking all the directories below.
+///
+/// Files modified in place don't change the mtime of their directory, so
+/// their new size is only seen once an entry of the directory changes.
+#[derive(Debug, Clone, Default)]
+pub struct ScanIndex {
+    root: PathBuf,
+    dirs: HashMap<PathBuf, IndexedDir>,
+}
+
+impl ScanIndex {
+    pub fn new(root: &Path) -> Self {
+        Self {
+            root: root.to_path_buf(),
+            dirs: HashMap::new(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.dirs.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.dirs.is_empty()
+    }
+
+    pub fn get(&self, path: &Path) -> Option<&IndexedDir> {
+        self.dirs.get(path)
+    }
+
+    pub fn insert(&mut self, path: PathBuf, dir: IndexedDir) {
+        self.dirs.insert(path, dir);
+    }
+
+    /// Where the index of scans of `root` is stored, in `$XDG_CACHE_HOME/provis`.
+    ///
+    /// Scans with and without `--all` don't count the same files, so they
+    /// get distinct indexes.
+    pub fn default_file(root: &Path, show_all: bool) -> Option<PathBuf> {
+        let cache_dir = std::env::var_os("XDG_CACHE_HOME")
+            .filter(|dir| !dir.is_empty())
+            .map(PathBuf::from)
+            .or_else(|| std::env::var_os("HOME").map(|home| Path::new(&home).join(".cache")))?;
+        let root = canonical(root);
+        let mut hash = fnv1a(root.as_os_str().as_bytes());
+        if show_all {
+            hash = fnv1a_continue(hash, b"\0all");
+        }
+        Some(cache_dir.join("provis").join(format!("index-{:016x}", hash)))
+    }
+
+    /// Read the index saved in `file`, expecting it to be about `root`
+    pub fn load(file: &Path, root: &Path) -> io::Result<Self> {
+        Self::read_from(BufReader::new(File::open(file)?), root)
+    }
+
+    /// Write the index to `file`, replacing it atomically
+    pub fn save(&self, file: &Path) -> io::Result<()> {
+        if let Some(dir) = file.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let tmp = file.with_extension("tmp");
+        let mut w = BufWriter::new(File::create(&tmp)?);
+        self.write_to(&mut w)?;
+        w.into_inner().map_err(|e| e.into_error())?.sync_all()?;
+        fs::rename(&tmp, file)
+    }
+
+    /// Write one line per directory followed by one `/name` line per subdirectory
+    fn write_to<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        writeln!(w, "{}", HEADER)?;
+        w.write_all(&escape(canonical(&self.root).as_os_str()))?;
+        writeln!(w)?;
+        for (path, dir) in &self.dirs {
+            let rel = path.strip_prefix(&self.root).unwrap_or(path);
+            write!(
+                w,
+                "{} {} {} {} {} {} {} ",
+                dir.stamp.dev,
+                dir.stamp.ino,
+                dir.stamp.mtime,
+                dir.stamp.mtime_nsec,
+                dir.own_size,
+                dir.own_files,
+                time_to_string(dir.own_modified),
+            )?;
+            w.write_all(&escape(rel.as_os_str()))?;
+            writeln!(w)?;
+            for name in &dir.subdirs {
+                w.write_all(b"/")?;
+                w.write_all(&escape(name))?;
+                writeln!(w)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn read_from<R: BufRead>(r: R, root: &Path) -> io::Result<Self> {
+        let mut lines = r.split(b'\n');
+        let mut next_line = || -> io::Result<Option<Vec<u8>>> { lines.next().transpose() };
+        if next_line()?.as_deref() != Some(HEADER.as_bytes()) {
+            return Err(invalid("not a provis index"));
+        }
+        let indexed_root = next_line()?.ok_or_else(|| invalid("missing root"))?;
+        if unescape(&indexed_root) != canonical(root).as_os_str() {
+            return Err(invalid("index of another root"));
+        }
+        let mut index = Self::new(root);
+        let mut current: Option<(PathBuf, IndexedDir)> = None;
+        while let Some(line) = next_line()? {
+            if let Some(name) = line.strip_prefix(b"/") {
+                let (_, dir) = current.as_mut().ok_or_else(|| invalid("orphan subdirectory"))?;
+                dir.subdirs.push(unescape(name));
+                continue;
+            }
+            if let Some((path, dir)) = current.take() {
+                index.insert(path, dir);
+            }
+            current = Some(parse_dir_line(&line, root).ok_or_else(|| invalid("bad directory line"))?);
+        }
+        if let Some((path, dir)) = current {
+            index.insert(path, dir);
+        }
+        Ok(index)
+    }
+}
+
+fn parse_dir_line(line: &[u8], root: &Path) -> Option<(PathBuf, IndexedDir)> {
+    let fields: Vec<&[u8]> = line.splitn(8, |&b| b == b' ').collect();
+    let [dev, ino, mtime, mtime_nsec, own_size, own_files, own_modified, path] = fields[..]
+    else {
+        return None;
+    };
+    let stamp = DirStamp {
+        dev: parse_field(dev)?,
+        ino: parse_field(ino)?,
+        mtime: parse_field(mtime)?,
+        mtime_nsec: parse_field(mtime_nsec)?,
+    };
+    Some((
+        root.join(unescape(path)),
+        IndexedDir {
+            stamp,
+            own_size: parse_field(own_size)?,
+            own_files: parse_field(own_files)?,
+            own_modified: time_from_str(std::str::from_utf8(own_modified).ok()?)?,
+            subdirs: Vec::new(),
+        },
+    ))
+}
+
+/// The root as written in the index and hashed in its file name, so that
+/// relative and symlinked paths to the same directory share their index.
+///
+/// The paths of the directories stay relative to the root as given
+fn canonical(root: &Path) -> PathBuf {
+    fs::canonicalize(root).unwrap_or_else(|_| root.to_path_buf())
+}
+
+fn parse_field<T: FromStr>(field: &[u8]) -> Option<T> {
+    std::str::from_utf8(field).ok()?.parse().ok()
+}
+
+fn invalid(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg.to_string())
+}
+
+fn time_to_string(time: Option<SystemTime>) -> String {
+    match time.and_then(|t| t.duration_since(UNIX_EPOCH).ok()) {
+        Some(d) => format!("{}.{:09}", d.as_secs(), d.subsec_nanos()),
+        None => "-".to_string(),
+    }
+}
+
+/// Parse a time written by `time_to_string`, `Some(None)` standing for "no time"
+fn time_from_str(s: &str) -> Option<Option<SystemTime>> {
+    if s == "-" {
+        return Some(None);
+    }
+    let (secs, nanos) = s.split_once('.')?;
+    let d = Duration::new(secs.parse().ok()?, nanos.parse().ok()?);
+    Some(Some(UNIX_EPOCH + d))
+}
+
+/// Escape the bytes which would break the line based format
+fn escape(s: &OsStr) -> Vec<u8> {
+    let mut escaped = Vec::with_capacity(s.len());
+    for &b in s.as_bytes() {
+        if b == b'%' || b == b'\n' || b == b'\r' {
+            escaped.extend_from_slice(format!("%{:02X}", b).as_bytes());
+        } else {
+            escaped.push(b);
+        }
+    }
+    escaped
+}
+
+fn unescape(s: &[u8]) -> OsString {
+    let mut bytes = Vec::with_capacity(s.len());
+    let mut i = 0;
+    while i < s.len() {
+        if s[i] == b'%' && i + 2 < s.len() {
+            let hex = std::str::from_utf8(&s[i + 1..i + 3]).ok();
+            if let Some(b) = hex.and_then(|h| u8::from_str_radix(h, 16).ok()) {
+                bytes.push(b);
+                i += 3;
+                continue;
+            }
+        }
+        bytes.push(s[i]);
+        i += 1;
+    }
+    OsString::from_vec(bytes)
+}
+
+fn fnv1a(bytes: &[u8]) -> u64 {
+    fnv1a_continue(0xcbf2_9ce4_8422_2325, bytes)
+}
+
+fn fnv1a_continue(mut hash: u64, bytes: &[u8]) -> u64 {
+    for &b in bytes {
+        hash ^= b as u64;
+        hash = hash.wrapping_mul(0x0100_0000_01b3);
+    }
+    hash
+}
+
+#[test]
+fn test_index_round_trip() {
+    let root = Path::new("/srv/share");
+    let mut index = ScanIndex::new(root);
+    let dir = IndexedDir {
+        stamp: DirStamp {
+            dev: 2049,
+            ino: 1234,
+            mtime: 1_700_000_000,
+            mtime_nsec: 42,
+        },
+        own_size: 1000,
+        own_files: 2,
+        own_modified: Some(UNIX_EPOCH + Duration::new(1_600_000_000, 5)),
+        subdirs: vec!["a b".into(), "50%\nodd".into()],
+    };
+    index.insert(root.to_path_buf(), dir.clone());
+    let sub = IndexedDir {
+        own_modified: None,
+        subdirs: vec![],
+        ..dir.clone()
+    };
+    index.insert(root.join("a b"), sub.clone());
+    let mut bytes = Vec::new();
+    index.write_to(&mut bytes).unwrap();
+    let read = ScanIndex::read_from(&bytes[..], root).unwrap();
+    assert_eq!(read.len(), 2);
+    assert_eq!(read.get(root), Some(&dir));
+    assert_eq!(read.get(&root.join("a b")), Some(&sub));
+    assert!(ScanIndex::read_from(&bytes[..], Path::new("/elsewhere")).is_err());
+}
+
+#[test]
+fn test_index_canonical_root() {
+    let dir = std::env::temp_dir().join(format!("provis-index-test-{}", std::process::id()));
+    fs::create_dir_all(dir.join("real/sub")).unwrap();
+    std::os::unix::fs::symlink(dir.join("real"), dir.join("link")).unwrap();
+    let metadata = fs::metadata(dir.join("real/sub")).unwrap();
+    let mut index = ScanIndex::new(&dir.join("link"));
+    let sub = IndexedDir {
+        stamp: DirStamp::of(&metadata),
+        own_size: 10,
+        own_files: 1,
+        own_modified: None,
+        subdirs: vec![],
+    };
+    index.insert(dir.join("link/sub"), sub.clone());
+    let mut bytes = Vec::new();
+    index.write_to(&mut bytes).unwrap();
+    let read = ScanIndex::read_from(&bytes[..], &dir.join("real"));
+    let with_dots = ScanIndex::read_from(&bytes[..], &dir.join("real/sub/.."));
+    assert_eq!(
+        ScanIndex::default_file(&dir.join("link"), false),
+        ScanIndex::default_file(&dir.join("real"), false),
+    );
+    fs::remove_dir_all(&dir).unwrap();
+    assert_eq!(read.unwrap().get(&dir.join("real/sub")), Some(&sub));
+    assert!(with_dots.is_ok());
+}
@@ -1,5 +1,26 @@
+pub mod ages;
+pub mod artifacts;
+pub mod browser;
 pub mod scanner;
+pub mod tree;
 pub mod display;
+pub mod duplicates;
+pub mod errors;
+pub mod index;
+pub mod ncdu;
+pub mod output;
+pub mod owners;
+pub mod progress;
+pub mod reader;
+pub mod types;
 
-pub use scanner::{DirectoryEntry, DirectoryScanner};
+pub use scanner::{DirectoryEntry, DirectoryScanner, FileEntry, ScanResult};
+pub use ages::AgeBreakdown;
+pub use artifacts::{Artifact, ArtifactKind};
 pub use display::display_directories;
+pub use tree::DirNode;
+pub use duplicates::{DuplicateCandidates, DuplicateSet};
+pub use errors::{ScanError, ScanErrorKind, ScanErrors};
+pub use index::ScanIndex;
+pub use owners::{OwnerBreakdown, OwnerStats};
+pub use types::{FileCategory, TypeBreakdown, TypeStats};
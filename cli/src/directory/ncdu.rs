@@ -0,0 +1,423 @@
+use {
+    crate::directory::{DirNode, tree::{FileLeaf, HardLink}},
+    serde_json::{Map, Value},
+    std::{
+        ffi::{OsStr, OsString},
+        fmt,
+        fs::{self, File},
+        io::{self, BufWriter, Write},
+        os::unix::ffi::{OsStrExt, OsStringExt},
+        path::Path,
+        time::{Duration, SystemTime, UNIX_EPOCH},
+    },
+};
+
+/// Error while reading a dump in the ncdu format
+#[derive(Debug)]
+pub enum NcduError {
+    Io(io::Error),
+    Json(serde_json::Error),
+    Format(&'static str),
+}
+
+impl fmt::Display for NcduError {
+    fn fmt(
+        &self,
+        f: &mut fmt::Formatter<'_>,
+    ) -> fmt::Result {
+        match self {
+            Self::Io(e) => write!(f, "{}", e),
+            Self::Json(e) => write!(f, "invalid JSON: {}", e),
+            Self::Format(s) => write!(f, "not an ncdu dump: {}", s),
+        }
+    }
+}
+impl std::error::Error for NcduError {}
+
+impl From<io::Error> for NcduError {
+    fn from(e: io::Error) -> Self {
+        Self::Io(e)
+    }
+}
+impl From<serde_json::Error> for NcduError {
+    fn from(e: serde_json::Error) -> Self {
+        Self::Json(e)
+    }
+}
+
+/// Write the ncdu dump (as written by `ncdu -o`) of a tree holding its files.
+///
+/// Like ncdu, names are written as their raw bytes, even when they're not
+/// valid UTF-8. Only apparent sizes are known, so they're also given as disk sizes.
+pub fn write_ncdu<W: Write>(
+    w: &mut W,
+    root: &DirNode,
+) -> io::Result<()> {
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    write!(
+        w,
+        r#"[1,2,{{"progname":"provis","progver":"{}","timestamp":{}}},"#,
+        env!("CARGO_PKG_VERSION"),
+        timestamp,
+    )?;
+    write_dir(w, root)?;
+    writeln!(w, "]")
+}
+
+/// Write the ncdu dump of a tree holding its files
+pub fn export_ncdu(
+    file: &Path,
+    root: &DirNode,
+) -> io::Result<()> {
+    let mut w = BufWriter::new(File::create(file)?);
+    write_ncdu(&mut w, root)?;
+    w.flush()
+}
+
+/// Read a dump written by `ncdu -o` (or by provis)
+pub fn import_ncdu(file: &Path) -> Result<DirNode, NcduError> {
+    let dump: Value = serde_json::from_str(&decode_raw(&fs::read(file)?))?;
+    tree_from_ncdu(&dump)
+}
+
+/// A directory is an array whose first item describes the directory
+/// itself, the other ones being its files and subdirectories
+fn write_dir<W: Write>(
+    w: &mut W,
+    node: &DirNode,
+) -> io::Result<()> {
+    write!(w, "[")?;
+    write_entry(w, &node.name, None, None, None)?;
+    for file in &node.files {
+        write!(w, ",")?;
+        write_entry(w, &file.name, Some(file.size), file.modified, file.link)?;
+    }
+    for child in &node.children {
+        write!(w, ",")?;
+        write_dir(w, child)?;
+    }
+    // entries left out of the scan, as ncdu writes those matching an exclude pattern
+    for name in &node.excluded {
+        write!(w, r#",{{"name":""#)?;
+        write_raw_string(w, name.as_bytes())?;
+        write!(w, r#"","excluded":"pattern"}}"#)?;
+    }
+    write!(w, "]")
+}
+
+fn write_entry<W: Write>(
+    w: &mut W,
+    name: &OsStr,
+    size: Option<u64>,
+    modified: Option<SystemTime>,
+    link: Option<HardLink>,
+) -> io::Result<()> {
+    write!(w, r#"{{"name":""#)?;
+    write_raw_string(w, name.as_bytes())?;
+    write!(w, "\"")?;
+    if let Some(size) = size {
+        write!(w, r#","asize":{},"dsize":{}"#, size, size)?;
+    }
+    if let Some(link) = link {
+        write!(w, r#","ino":{},"nlink":{},"hlnkc":true"#, link.ino, link.nlink)?;
+    }
+    if let Some(d) = modified.and_then(|t| t.duration_since(UNIX_EPOCH).ok()) {
+        write!(w, r#","mtime":{}"#, d.as_secs())?;
+    }
+    write!(w, "}}")
+}
+
+/// Write the content of a JSON string, escaping what JSON requires
+/// but keeping the other bytes as they are, as ncdu does
+fn write_raw_string<W: Write>(
+    w: &mut W,
+    bytes: &[u8],
+) -> io::Result<()> {
+    for &b in bytes {
+        match b {
+            b'"' => w.write_all(b"\\\"")?,
+            b'\\' => w.write_all(b"\\\\")?,
+            0..=0x1f | 0x7f => write!(w, "\\u{:04x}", b)?,
+            _ => w.write_all(&[b])?,
+        }
+    }
+    Ok(())
+}
+
+/// Start of the private use characters standing for the bytes of
+/// names which aren't valid UTF-8, while the dump is parsed
+static RAW_BYTE_BASE: u32 = 0xF700;
+
+/// Private use character put before the characters of the dump which
+/// could be taken for a raw byte (or for this escape), so that they're kept
+static RAW_ESCAPE: char = '\u{F800}';
+
+fn needs_raw_escape(code: u32) -> bool {
+    (RAW_BYTE_BASE..=RAW_ESCAPE as u32).contains(&code)
+}
+
+/// Make a dump parsable as JSON, replacing each byte which isn't
+/// part of valid UTF-8 with a private use character, and escaping the
+/// characters of this range already in the dump, written as they are
+/// or as `\u` sequences
+fn decode_raw(mut bytes: &[u8]) -> String {
+    let mut s = String::with_capacity(bytes.len());
+    loop {
+        let (valid, invalid) = match std::str::from_utf8(bytes) {
+            Ok(valid) => (valid, &[][..]),
+            Err(e) => {
+                let (valid, rest) = bytes.split_at(e.valid_up_to());
+                let invalid = e.error_len().unwrap_or(rest.len());
+                bytes = &rest[invalid..];
+                (std::str::from_utf8(valid).unwrap_or_default(), &rest[..invalid])
+            }
+        };
+        let mut chars = valid.chars();
+        while let Some(c) = chars.next() {
+            if c == '\\' {
+                // an escape sequence, which is copied whole
+                s.push(c);
+                let Some(next) = chars.next() else {
+                    break;
+                };
+                if next == 'u' {
+                    let hex: String = chars.clone().take(4).collect();
+                    if u32::from_str_radix(&hex, 16).is_ok_and(needs_raw_escape) {
+                        s.push_str("uf800\\");
+                    }
+                }
+                s.push(next);
+                continue;
+            }
+            if needs_raw_escape(c as u32) {
+                s.push(RAW_ESCAPE);
+            }
+            s.push(c);
+        }
+        if invalid.is_empty() {
+            return s;
+        }
+        for &b in invalid {
+            s.extend(char::from_u32(RAW_BYTE_BASE + b as u32));
+        }
+    }
+}
+
+/// Give back their raw bytes to the names decoded by `decode_raw`
+fn encode_raw(s: &str) -> OsString {
+    let mut bytes = Vec::with_capacity(s.len());
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        let c = if c == RAW_ESCAPE {
+            match chars.next() {
+                Some(escaped) => escaped,
+                None => break,
+            }
+        } else {
+            match (c as u32).checked_sub(RAW_BYTE_BASE) {
+                Some(b) if b < 0x100 => {
+                    bytes.push(b as u8);
+                    continue;
+                }
+                _ => c,
+            }
+        };
+        bytes.extend_from_slice(c.encode_utf8(&mut [0; 4]).as_bytes());
+    }
+    OsString::from_vec(bytes)
+}
+
+pub fn tree_from_ncdu(dump: &Value) -> Result<DirNode, NcduError> {
+    let items = dump.as_array().ok_or(NcduError::Format("not an array"))?;
+    if items.first().and_then(Value::as_u64) != Some(1) {
+        return Err(NcduError::Format("unsupported major version"));
+    }
+    let root = items.get(3).ok_or(NcduError::Format("no root directory"))?;
+    dir_from_ncdu(root)?.ok_or(NcduError::Format("excluded root directory"))
+}
+
+/// Read a directory, or `None` when ncdu didn't scan it
+fn dir_from_ncdu(value: &Value) -> Result<Option<DirNode>, NcduError> {
+    let items = value
+        .as_array()
+        .ok_or(NcduError::Format("directory isn't an array"))?;
+    let (info, entries) = items
+        .split_first()
+        .ok_or(NcduError::Format("empty directory array"))?;
+    let info = info
+        .as_object()
+        .ok_or(NcduError::Format("directory without info"))?;
+    if info.contains_key("excluded") {
+        return Ok(None);
+    }
+    let mut node = DirNode::new(entry_name(info)?);
+    for entry in entries {
+        match entry {
+            Value::Array(_) => {
+                match dir_from_ncdu(entry)? {
+                    Some(child) => {
+                        node.size += child.size;
+                        node.file_count += child.file_count;
+                        node.modified = node.modified.max(child.modified);
+                        node.children.push(child);
+                    }
+                    None => {
+                        let info = entry[0].as_object().ok_or(NcduError::Format("directory without info"))?;
+                        node.excluded.push(entry_name(info)?);
+                    }
+                }
+            }
+            Value::Object(obj) => {
+                // only regular files are counted by provis scans
+                let not_regular = obj.get("notreg").and_then(Value::as_bool).unwrap_or(false);
+                if obj.contains_key("excluded") {
+                    node.excluded.push(entry_name(obj)?);
+                    continue;
+                }
+                if not_regular {
+                    continue;
+                }
+                let size = obj
+                    .get("asize")
+                    .or_else(|| obj.get("dsize"))
+                    .and_then(Value::as_u64)
+                    .unwrap_or(0);
+                let modified = obj
+                    .get("mtime")
+                    .and_then(Value::as_u64)
+                    .map(|secs| UNIX_EPOCH + Duration::from_secs(secs));
+                node.own_size += size;
+                node.file_count += 1;
+                node.modified = node.modified.max(modified);
+                let link = obj
+                    .get("hlnkc")
+                    .and_then(Value::as_bool)
+                    .unwrap_or(false)
+                    .then(|| {
+                        Some(HardLink {
+                            ino: obj.get("ino")?.as_u64()?,
+                            nlink: obj.get("nlink").and_then(Value::as_u64).unwrap_or(2),
+                        })
+                    })
+                    .flatten();
+                node.files.push(FileLeaf {
+                    name: entry_name(obj)?,
+                    size,
+                    modified,
+                    link,
+                });
+            }
+            _ => return Err(NcduError::Format("unexpected entry")),
+        }
+    }
+    node.size += node.own_size;
+    Ok(Some(node))
+}
+
+fn entry_name(obj: &Map<String, Value>) -> Result<OsString, NcduError> {
+    obj.get("name")
+        .and_then(Value::as_str)
+        .map(encode_raw)
+        .ok_or(NcduError::Format("entry without name"))
+}
+
+#[test]
+fn test_ncdu_round_trip() {
+    let dump: Value = serde_json::from_str(
+        r#"[1,2,{"progname":"ncdu","progver":"1.19","timestamp":1700000000},
+        [{"name":"/data","asize":4096,"dsize":4096,"dev":2049},
+            {"name":"a.log","asize":1000,"dsize":4096,"mtime":1600000000},
+            {"name":"fifo","notreg":true},
+            [{"name":"sub","asize":4096},
+                {"name":"b.bin","asize":5000,"dsize":8192}
+            ],
+            [{"name":"proc","excluded":"kernfs"}]
+        ]]"#,
+    )
+    .unwrap();
+    let tree = tree_from_ncdu(&dump).unwrap();
+    assert_eq!(tree.name, "/data");
+    assert_eq!((tree.size, tree.own_size, tree.file_count), (6000, 1000, 2));
+    assert_eq!(tree.children.len(), 1);
+    assert_eq!(tree.children[0].size, 5000);
+    assert_eq!(tree.excluded, vec![OsString::from("proc")]);
+    assert_eq!(tree.modified, Some(UNIX_EPOCH + Duration::from_secs(1_600_000_000)));
+    let mut tree = tree;
+    tree.files.push(FileLeaf {
+        name: OsString::from_vec(b"caf\xe9 \"1\"\n".to_vec()),
+        size: 0,
+        modified: None,
+        link: Some(HardLink { ino: 42, nlink: 3 }),
+    });
+    tree.file_count += 1;
+    let mut exported = Vec::new();
+    write_ncdu(&mut exported, &tree).unwrap();
+    assert!(std::str::from_utf8(&exported).is_err()); // the raw name
+    let exported: Value = serde_json::from_str(&decode_raw(&exported)).unwrap();
+    assert_eq!(exported[3][2]["hlnkc"], true);
+    assert_eq!(tree_from_ncdu(&exported).unwrap(), tree);
+    assert!(tree_from_ncdu(&serde_json::json!([2, 0, {}, []])).is_err());
+    // private use characters in valid names aren't taken for raw bytes
+    let private = "\u{F741}\u{F800}x";
+    let mut tree = DirNode::new(OsString::from("/p"));
+    tree.files.push(FileLeaf { name: OsString::from(private), size: 1, modified: None, link: None });
+    tree.size = 1;
+    tree.own_size = 1;
+    tree.file_count = 1;
+    let mut exported = Vec::new();
+    write_ncdu(&mut exported, &tree).unwrap();
+    let exported: Value = serde_json::from_str(&decode_raw(&exported)).unwrap();
+    assert_eq!(tree_from_ncdu(&exported).unwrap(), tree);
+    let escaped = [
+        br#"[1,2,{},[{"name":"/p"},{"name":"\uf741\\uf741\u0041"#.as_slice(),
+        b"\xff",
+        br#"","asize":1}]]"#,
+    ]
+    .concat();
+    let dump: Value = serde_json::from_str(&decode_raw(&escaped)).unwrap();
+    let name = tree_from_ncdu(&dump).unwrap().files[0].name.clone();
+    assert_eq!(name.as_bytes(), ["\u{F741}".as_bytes(), br"\uf741A", b"\xff"].concat());
+}
+
+#[test]
+fn test_export_scan() {
+    use {
+        super::scanner::DirectoryScanner,
+        std::fs,
+    };
+    let dir = std::env::temp_dir().join(format!("provis-ncdu-test-{}", std::process::id()));
+    fs::create_dir_all(dir.join("app/node_modules/lib")).unwrap();
+    fs::create_dir_all(dir.join(".cache")).unwrap();
+    fs::write(dir.join("app/package.json"), "{}").unwrap();
+    fs::write(dir.join("app/node_modules/lib/index.js"), "x".repeat(1000)).unwrap();
+    fs::write(dir.join(".cache/blob"), "y".repeat(500)).unwrap();
+    let export = |reclaimable: bool| {
+        let result = DirectoryScanner::new(&dir)
+            .recursive()
+            .build_tree(None)
+            .tree_files(true)
+            .track_artifacts(reclaimable)
+            .scan()
+            .unwrap();
+        let mut exported = Vec::new();
+        write_ncdu(&mut exported, result.tree.as_ref().unwrap()).unwrap();
+        let dump: Value = serde_json::from_str(&decode_raw(&exported)).unwrap();
+        (result.total_size, dump)
+    };
+    // skipped directories are in the dump, as excluded
+    let (total_size, dump) = export(false);
+    assert_eq!(total_size, 2);
+    let tree = tree_from_ncdu(&dump).unwrap();
+    assert_eq!(tree.size, total_size);
+    assert!(tree.excluded.contains(&OsString::from(".cache")));
+    let app = tree.children.iter().find(|c| c.name == "app").unwrap();
+    assert_eq!(app.excluded, vec![OsString::from("node_modules")]);
+    // measured artifacts are in the dump with their content
+    let (total_size, dump) = export(true);
+    assert_eq!(total_size, 1502);
+    assert_eq!(tree_from_ncdu(&dump).unwrap().size, total_size);
+    fs::remove_dir_all(&dir).unwrap();
+}
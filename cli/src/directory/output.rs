@@ -0,0 +1,201 @@
+use {
+    crate::{
+        Args,
+        csv::Csv,
+        directory::ScanResult,
+    },
+    serde_json::{Map, Value, json},
+    std::{
+        io::{self, Write},
+        path::Path,
+        time::UNIX_EPOCH,
+    },
+};
+
+/// Insert the path under `key`, and, when it isn't valid UTF-8, its raw
+/// bytes in base64 under `<key>-base64` so that it can be recovered
+pub fn insert_path(
+    obj: &mut Map<String, Value>,
+    key: &str,
+    path: &Path,
+) {
+    obj.insert(key.to_string(), Value::String(path.to_string_lossy().to_string()));
+    if let Some(raw) = non_utf8_base64(path) {
+        obj.insert(format!("{key}-base64"), Value::String(raw));
+    }
+}
+
+/// A JSON value for a path: a plain string when the path is valid UTF-8,
+/// else an object with the lossy string and the base64 encoded raw bytes
+pub fn path_value(path: &Path) -> Value {
+    match non_utf8_base64(path) {
+        None => Value::String(path.to_string_lossy().to_string()),
+        Some(raw) => json!({
+            "path": path.to_string_lossy(),
+            "path-base64": raw,
+        }),
+    }
+}
+
+/// Return the base64 encoding of the raw bytes of the path, unless it's valid UTF-8
+pub fn non_utf8_base64(path: &Path) -> Option<String> {
+    if path.to_str().is_some() {
+        return None;
+    }
+    #[cfg(unix)]
+    {
+        use std::os::unix::ffi::OsStrExt;
+        Some(base64(path.as_os_str().as_bytes()))
+    }
+    #[cfg(not(unix))]
+    {
+        Some(base64(path.to_string_lossy().as_bytes()))
+    }
+}
+
+/// Standard base64, with padding
+fn base64(bytes: &[u8]) -> String {
+    static ALPHABET: &[u8; 64] =
+        b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut s = String::with_capacity((bytes.len() + 2) / 3 * 4);
+    for chunk in bytes.chunks(3) {
+        let n = chunk
+            .iter()
+            .enumerate()
+            .fold(0u32, |n, (i, &b)| n | (b as u32) << (16 - 8 * i));
+        for i in 0..4 {
+            if i <= chunk.len() {
+                s.push(ALPHABET[(n >> (18 - 6 * i) & 63) as usize] as char);
+            } else {
+                s.push('=');
+            }
+        }
+    }
+    s
+}
+
+fn share(part: u64, total: u64) -> f64 {
+    if total == 0 {
+        0.0
+    } else {
+        part as f64 / total as f64
+    }
+}
+
+/// Build the JSON value of a scan, with at most `limit` directories and files.
+///
+/// `truncated` tells whether some directories may be missing from the list,
+/// because of the limit or of the minimal size, so that the scan can't be
+/// used to tell a directory doesn't exist
+pub fn scan_to_json(
+    base_path: &Path,
+    result: &ScanResult,
+    limit: usize,
+    min_size: u64,
+) -> Value {
+    let directories = result
+        .directories
+        .iter()
+        .take(limit)
+        .map(|e| {
+            let mut obj = Map::new();
+            insert_path(&mut obj, "path", &e.path);
+            obj.insert("size".into(), e.size.into());
+            obj.insert("depth".into(), e.depth.into());
+            obj.insert("files".into(), e.file_count.into());
+            obj.insert("share".into(), share(e.size, result.total_size).into());
+            obj.insert("modified".into(), unix_seconds(e.modified));
+            Value::Object(obj)
+        })
+        .collect();
+    let files = result
+        .files
+        .iter()
+        .take(limit)
+        .map(|e| {
+            let mut obj = Map::new();
+            insert_path(&mut obj, "path", &e.path);
+            obj.insert("size".into(), e.size.into());
+            obj.insert("share".into(), share(e.size, result.total_size).into());
+            obj.insert("modified".into(), unix_seconds(e.modified));
+            Value::Object(obj)
+        })
+        .collect();
+    let mut obj = Map::new();
+    insert_path(&mut obj, "path", base_path);
+    obj.insert("total-size".into(), result.total_size.into());
+    obj.insert("total-files".into(), result.total_files.into());
+    // the scanner already kept only the `limit` biggest directories
+    let truncated = result.directories.len() >= limit || min_size > 0;
+    obj.insert("truncated".into(), truncated.into());
+    obj.insert("directories".into(), Value::Array(directories));
+    obj.insert("files".into(), Value::Array(files));
+    obj.insert("errors".into(), result.errors.to_json());
+    Value::Object(obj)
+}
+
+fn unix_seconds(time: Option<std::time::SystemTime>) -> Value {
+    time.and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+        .map_or(Value::Null, |d| d.as_secs().into())
+}
+
+/// Write the directories then the files of a scan as CSV, with at
+/// most `limit` rows of each kind
+pub fn write_scan_csv<W: Write>(
+    w: &mut W,
+    result: &ScanResult,
+    limit: usize,
+    show_dirs: bool,
+    show_files: bool,
+    args: &Args,
+) -> io::Result<()> {
+    let mut csv = Csv::new(args.csv_separator, w);
+    for title in ["kind", "path", "size", "depth", "files", "share", "path-base64"] {
+        csv.cell(title)?;
+    }
+    csv.end_line()?;
+    if show_dirs {
+        for e in result.directories.iter().take(limit) {
+            csv.cell("dir")?;
+            csv.cell(e.path.to_string_lossy())?;
+            csv.cell(e.size)?;
+            csv.cell(e.depth)?;
+            csv.cell(e.file_count)?;
+            csv.cell(share(e.size, result.total_size))?;
+            csv.cell_opt(non_utf8_base64(&e.path))?;
+            csv.end_line()?;
+        }
+    }
+    if show_files {
+        for e in result.files.iter().take(limit) {
+            csv.cell("file")?;
+            csv.cell(e.path.to_string_lossy())?;
+            csv.cell(e.size)?;
+            csv.cell_opt(None::<usize>)?;
+            csv.cell(1)?;
+            csv.cell(share(e.size, result.total_size))?;
+            csv.cell_opt(non_utf8_base64(&e.path))?;
+            csv.end_line()?;
+        }
+    }
+    Ok(())
+}
+
+#[test]
+fn test_base64() {
+    assert_eq!(base64(b""), "");
+    assert_eq!(base64(b"f"), "Zg==");
+    assert_eq!(base64(b"fo"), "Zm8=");
+    assert_eq!(base64(b"foo"), "Zm9v");
+    assert_eq!(base64(b"foob"), "Zm9vYg==");
+    assert_eq!(base64(&[0xff, 0xfe, 0x80]), "//6A");
+}
+
+#[cfg(unix)]
+#[test]
+fn test_non_utf8_path() {
+    use std::{ffi::OsStr, os::unix::ffi::OsStrExt};
+    let path = Path::new(OsStr::from_bytes(b"/tmp/caf\xe9"));
+    assert_eq!(non_utf8_base64(path).as_deref(), Some("L3RtcC9jYWbp"));
+    assert_eq!(non_utf8_base64(Path::new("/tmp/café")), None);
+}
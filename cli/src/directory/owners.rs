@@ -0,0 +1,277 @@
+use {
+    crate::{
+        Args,
+        directory::{
+            TypeStats,
+            display::{format_bytes, make_skin},
+            output::insert_path,
+        },
+        process::graph::render_bar_compact,
+    },
+    serde_json::{Map, Value, json},
+    std::{
+        cmp::Reverse,
+        collections::HashMap,
+        fs,
+        io::{self, Write},
+        path::{Path, PathBuf},
+    },
+    termimad::minimad::{OwningTemplateExpander, TableBuilder, Col, Alignment},
+};
+
+static PASSWD_FILE: &str = "/etc/passwd";
+static GROUP_FILE: &str = "/etc/group";
+
+/// What a user, or a group, owns in the scanned tree
+#[derive(Debug, Clone, Default)]
+pub struct OwnerStats {
+    pub stats: TypeStats,
+    /// bytes by directory, at the depth the breakdown was asked for
+    pub dirs: HashMap<PathBuf, u64>,
+}
+
+impl OwnerStats {
+    /// The directories holding most of the owner's bytes, biggest first
+    pub fn top_dirs(&self, limit: usize) -> Vec<(&Path, u64)> {
+        let mut dirs: Vec<(&Path, u64)> = self
+            .dirs
+            .iter()
+            .map(|(path, &bytes)| (path.as_path(), bytes))
+            .collect();
+        dirs.sort_by_key(|&(path, bytes)| (Reverse(bytes), path));
+        dirs.truncate(limit);
+        dirs
+    }
+}
+
+/// Scanned bytes and files, by owning user and by owning group
+#[derive(Debug, Clone)]
+pub struct OwnerBreakdown {
+    base_path: PathBuf,
+    /// depth, below the base path, of the directories the bytes are
+    /// also attributed to, `None` when they're not tracked
+    dir_depth: Option<usize>,
+    pub users: HashMap<u32, OwnerStats>,
+    pub groups: HashMap<u32, OwnerStats>,
+}
+
+impl OwnerBreakdown {
+    pub fn new(base_path: &Path, dir_depth: Option<usize>) -> Self {
+        Self {
+            base_path: base_path.to_path_buf(),
+            dir_depth,
+            users: HashMap::new(),
+            groups: HashMap::new(),
+        }
+    }
+    pub fn add(&mut self, path: &Path, uid: u32, gid: u32, size: u64) {
+        let dir = self.dir_depth.map(|depth| self.dir_of(path, depth));
+        for (owners, id) in [(&mut self.users, uid), (&mut self.groups, gid)] {
+            let owner = owners.entry(id).or_default();
+            owner.stats.bytes += size;
+            owner.stats.files += 1;
+            if let Some(dir) = &dir {
+                *owner.dirs.entry(dir.clone()).or_default() += size;
+            }
+        }
+    }
+    /// The directory, at most `depth` levels below the base, holding the file at `path`
+    fn dir_of(&self, path: &Path, depth: usize) -> PathBuf {
+        let parent = path.parent().unwrap_or(path);
+        match parent.strip_prefix(&self.base_path) {
+            Ok(relative) => {
+                let mut dir = self.base_path.clone();
+                dir.extend(relative.components().take(depth));
+                dir
+            }
+            Err(_) => parent.to_path_buf(),
+        }
+    }
+    pub fn total_bytes(&self) -> u64 {
+        self.users.values().map(|o| o.stats.bytes).sum()
+    }
+    pub fn to_json(&self, limit: usize, dirs_limit: usize) -> Value {
+        let names = OwnerNames::read();
+        let rows = |owners: &HashMap<u32, OwnerStats>, id_key: &str, names: &HashMap<u32, String>| {
+            let rows: Vec<Value> = sorted(owners)
+                .into_iter()
+                .take(limit)
+                .map(|(id, owner)| {
+                    let mut obj = Map::new();
+                    obj.insert(id_key.into(), id.into());
+                    obj.insert("name".into(), names.get(&id).map_or(Value::Null, |n| n.as_str().into()));
+                    obj.insert("bytes".into(), owner.stats.bytes.into());
+                    obj.insert("files".into(), owner.stats.files.into());
+                    if self.dir_depth.is_some() {
+                        let dirs: Vec<Value> = owner
+                            .top_dirs(dirs_limit)
+                            .into_iter()
+                            .map(|(path, bytes)| {
+                                let mut dir = Map::new();
+                                insert_path(&mut dir, "path", path);
+                                dir.insert("bytes".into(), bytes.into());
+                                Value::Object(dir)
+                            })
+                            .collect();
+                        obj.insert("dirs".into(), dirs.into());
+                    }
+                    Value::Object(obj)
+                })
+                .collect();
+            Value::Array(rows)
+        };
+        json!({
+            "total-bytes": self.total_bytes(),
+            "users": rows(&self.users, "uid", &names.users),
+            "groups": rows(&self.groups, "gid", &names.groups),
+        })
+    }
+}
+
+/// The owners, biggest first
+fn sorted(owners: &HashMap<u32, OwnerStats>) -> Vec<(u32, &OwnerStats)> {
+    let mut owners: Vec<(u32, &OwnerStats)> = owners.iter().map(|(&id, o)| (id, o)).collect();
+    owners.sort_by_key(|&(id, o)| (Reverse(o.stats.bytes), id));
+    owners
+}
+
+/// Names of the users and groups, as found in `/etc/passwd` and `/etc/group`
+#[derive(Debug, Clone, Default)]
+pub struct OwnerNames {
+    pub users: HashMap<u32, String>,
+    pub groups: HashMap<u32, String>,
+}
+
+impl OwnerNames {
+    /// Read the local databases. Missing files only make for
+    /// numeric IDs in the output
+    pub fn read() -> Self {
+        let read = |file: &str| {
+            fs::read_to_string(file)
+                .map(|content| parse_id_names(&content))
+                .unwrap_or_default()
+        };
+        Self {
+            users: read(PASSWD_FILE),
+            groups: read(GROUP_FILE),
+        }
+    }
+    fn user(&self, uid: u32) -> String {
+        self.users.get(&uid).cloned().unwrap_or_else(|| uid.to_string())
+    }
+    fn group(&self, gid: u32) -> String {
+        self.groups.get(&gid).cloned().unwrap_or_else(|| gid.to_string())
+    }
+}
+
+/// Parse the lines of `/etc/passwd` or `/etc/group`, both having
+/// the name in the first field and the numeric id in the third one
+fn parse_id_names(content: &str) -> HashMap<u32, String> {
+    let mut names = HashMap::new();
+    for line in content.lines() {
+        if line.starts_with('#') {
+            continue;
+        }
+        let mut fields = line.split(':');
+        let (Some(name), Some(_), Some(id)) = (fields.next(), fields.next(), fields.next()) else {
+            continue;
+        };
+        if let Ok(id) = id.parse::<u32>() {
+            // the first entry wins, as with getpwuid
+            names.entry(id).or_insert_with(|| name.to_string());
+        }
+    }
+    names
+}
+
+/// Write the users table, then the groups one, and the top
+/// directories of every listed user when they were tracked
+pub fn write_owners<W: Write>(
+    w: &mut W,
+    owners: &OwnerBreakdown,
+    limit: usize,
+    dirs_limit: usize,
+    args: &Args,
+) -> io::Result<()> {
+    let total = owners.total_bytes();
+    if total == 0 && owners.users.is_empty() {
+        return writeln!(w, "\nNo files found.");
+    }
+    let names = OwnerNames::read();
+    let pct = |bytes: u64| 100.0 * bytes as f32 / total.max(1) as f32;
+    for (title, map, is_user) in [
+        ("User", &owners.users, true),
+        ("Group", &owners.groups, false),
+    ] {
+        let mut expander = OwningTemplateExpander::new();
+        expander.set_default("");
+        for (id, owner) in sorted(map).into_iter().take(limit) {
+            let name = if is_user { names.user(id) } else { names.group(id) };
+            expander
+                .sub("rows")
+                .set_md("bar", render_bar_compact(pct(owner.stats.bytes), 10, args.ascii))
+                .set("size", format_bytes(owner.stats.bytes))
+                .set("files", owner.stats.files)
+                .set("id", id)
+                .set("name", name);
+        }
+        let mut tbl = TableBuilder::default();
+        tbl.col(Col::new("Usage", "${bar}").align_content(Alignment::Left))
+            .col(Col::new("Size", "${size}").align_content(Alignment::Right))
+            .col(Col::new("Files", "${files}").align_content(Alignment::Right))
+            .col(Col::new("Id", "${id}").align_content(Alignment::Right))
+            .col(Col::new(title, "${name}").align_content(Alignment::Left));
+        writeln!(w, "\nScanned Space by {}:\n", title)?;
+        make_skin(args).write_owning_expander_md(w, &expander, &tbl)?;
+    }
+
+    if owners.dir_depth.is_none() {
+        return Ok(());
+    }
+    let mut expander = OwningTemplateExpander::new();
+    expander.set_default("");
+    for (uid, owner) in sorted(&owners.users).into_iter().take(limit) {
+        let user_bytes = owner.stats.bytes.max(1);
+        for (path, bytes) in owner.top_dirs(dirs_limit) {
+            let share = 100.0 * bytes as f32 / user_bytes as f32;
+            expander
+                .sub("rows")
+                .set("user", names.user(uid))
+                .set_md("bar", render_bar_compact(share, 10, args.ascii))
+                .set("size", format_bytes(bytes))
+                .set("path", path.display());
+        }
+    }
+    let mut tbl = TableBuilder::default();
+    tbl.col(Col::new("User", "${user}").align_content(Alignment::Left))
+        .col(Col::new("Share", "${bar}").align_content(Alignment::Left))
+        .col(Col::new("Size", "${size}").align_content(Alignment::Right))
+        .col(Col::new("Directory", "${path}").align_content(Alignment::Left));
+    writeln!(w, "\nTop Directories by User:\n")?;
+    make_skin(args).write_owning_expander_md(w, &expander, &tbl)
+}
+
+#[test]
+fn test_owner_breakdown() {
+    let names = parse_id_names(
+        "# comment\nroot:x:0:0:root:/root:/bin/bash\nalice:x:1000:1000::/home/alice:/bin/sh\nbroken\ntoor:x:0:0::/:/bin/sh\n",
+    );
+    assert_eq!(names.get(&0).map(String::as_str), Some("root"));
+    assert_eq!(names.get(&1000).map(String::as_str), Some("alice"));
+    assert_eq!(names.len(), 2);
+
+    let mut owners = OwnerBreakdown::new(Path::new("/scratch"), Some(1));
+    owners.add(Path::new("/scratch/a/b/big"), 1000, 100, 300);
+    owners.add(Path::new("/scratch/a/small"), 1000, 100, 100);
+    owners.add(Path::new("/scratch/c/f"), 1000, 100, 50);
+    owners.add(Path::new("/scratch/top"), 0, 0, 10);
+    assert_eq!(owners.total_bytes(), 460);
+    let alice = &owners.users[&1000];
+    assert_eq!(alice.stats.files, 3);
+    assert_eq!(
+        alice.top_dirs(5),
+        vec![(Path::new("/scratch/a"), 400), (Path::new("/scratch/c"), 50)],
+    );
+    assert_eq!(owners.users[&0].top_dirs(5), vec![(Path::new("/scratch"), 10)]);
+    assert_eq!(owners.groups[&100].stats.bytes, 450);
+}
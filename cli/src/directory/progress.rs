@@ -0,0 +1,132 @@
+use {
+    crate::{
+        Args,
+        age::Age,
+        directory::display::format_bytes,
+    },
+    lfs_core::{DeviceId, ReadOptions},
+    std::{
+        io::{self, Write},
+        path::Path,
+        time::{Duration, Instant},
+    },
+};
+
+/// Minimal delay between two updates of the progress line
+static REFRESH_PERIOD: Duration = Duration::from_millis(250);
+
+/// A progress line on stderr, updated while a scan runs
+#[derive(Debug)]
+pub struct ScanProgress {
+    start: Instant,
+    last_print: Instant,
+    /// number of entries (files, directories, links...) expected in the scan
+    expected: Option<u64>,
+    dirs: u64,
+    files: u64,
+    bytes: u64,
+    /// length of the last printed line, to be overwritten
+    printed_len: usize,
+}
+
+impl ScanProgress {
+    pub fn new(expected: Option<u64>) -> Self {
+        let now = Instant::now();
+        Self {
+            start: now,
+            last_print: now,
+            expected,
+            dirs: 0,
+            files: 0,
+            bytes: 0,
+            printed_len: 0,
+        }
+    }
+    pub fn add_dir(&mut self) {
+        self.dirs += 1;
+        self.tick();
+    }
+    pub fn add_files(&mut self, files: u64, bytes: u64) {
+        self.files += files;
+        self.bytes += bytes;
+        self.tick();
+    }
+    fn tick(&mut self) {
+        let now = Instant::now();
+        if now.duration_since(self.last_print) >= REFRESH_PERIOD {
+            self.last_print = now;
+            let line = self.line(now.duration_since(self.start));
+            self.print(&line);
+        }
+    }
+    /// Build the progress line, for a scan running for `elapsed`
+    fn line(&self, elapsed: Duration) -> String {
+        let done = self.dirs + self.files;
+        let rate = done as f64 / elapsed.as_secs_f64().max(0.001);
+        let mut line = format!(
+            "Scanning... {} dirs, {} files, {}",
+            self.dirs,
+            self.files,
+            format_bytes(self.bytes),
+        );
+        if let Some(expected) = self.expected.filter(|&n| n > 0) {
+            // the expected count is the whole filesystem's, so the scan of
+            // a part of it can end before reaching 100%
+            let share = (done as f64 / expected as f64).min(0.99);
+            line.push_str(&format!(" - {:.0}%", share * 100.0));
+            if done < expected && rate > 0.0 {
+                let eta = Duration::from_secs_f64((expected - done) as f64 / rate);
+                line.push_str(&format!(" - {:.0} entries/s - ETA {}", rate, Age(eta)));
+                return line;
+            }
+        }
+        line.push_str(&format!(" - {:.0} entries/s", rate));
+        line
+    }
+    fn print(&mut self, line: &str) {
+        let len = line.chars().count();
+        let padding = self.printed_len.saturating_sub(len);
+        let mut stderr = io::stderr();
+        let _ = write!(stderr, "\r{}{}", line, " ".repeat(padding));
+        let _ = stderr.flush();
+        self.printed_len = len;
+    }
+    /// Erase the progress line
+    pub fn finish(&mut self) {
+        if self.printed_len > 0 {
+            eprint!("\r{}\r", " ".repeat(self.printed_len));
+            self.printed_len = 0;
+        }
+    }
+}
+
+/// Number of used inodes on the filesystem holding `path`, which
+/// is the number of entries a scan of the whole filesystem meets
+pub fn used_inodes(path: &Path, args: &Args) -> Option<u64> {
+    let dev = DeviceId::of_path(path).ok()?;
+    let mut options = ReadOptions::default();
+    if let Some(timeout) = args.timeout {
+        options = options.stats_timeout(timeout.as_duration());
+    }
+    let mounts = lfs_core::read_mounts(&options).ok()?;
+    let mount = mounts.iter().find(|m| m.info.dev == dev)?;
+    let inodes = mount.stats.as_ref().ok()?.inodes.as_ref()?;
+    Some(inodes.used())
+}
+
+#[test]
+fn test_progress_line() {
+    let mut progress = ScanProgress::new(Some(1000));
+    progress.dirs = 50;
+    progress.files = 200;
+    progress.bytes = 3 * 1024 * 1024;
+    assert_eq!(
+        progress.line(Duration::from_secs(10)),
+        "Scanning... 50 dirs, 200 files, 3.0M - 25% - 25 entries/s - ETA 30s",
+    );
+    let progress = ScanProgress::new(None);
+    assert_eq!(
+        progress.line(Duration::from_secs(1)),
+        "Scanning... 0 dirs, 0 files, 0B - 0 entries/s",
+    );
+}
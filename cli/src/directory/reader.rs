@@ -0,0 +1,127 @@
+use {
+    std::{
+        ffi::OsString,
+        fs::{self, Metadata},
+        io,
+        path::Path,
+        sync::mpsc::{self, Sender},
+        thread,
+        time::Duration,
+    },
+};
+
+type Job = Box<dyn FnOnce() + Send>;
+
+/// An entry of a listed directory
+#[derive(Debug)]
+pub struct ListedEntry {
+    pub name: OsString,
+    /// not following symlinks
+    pub metadata: io::Result<Metadata>,
+}
+
+/// The content of a directory, read at once
+#[derive(Debug, Default)]
+pub struct DirListing {
+    pub entries: Vec<ListedEntry>,
+    /// errors met while iterating over the entries
+    pub errors: Vec<io::Error>,
+}
+
+/// Reads directories and metadata, giving up on calls taking longer
+/// than the timeout, as happens on hung network mounts.
+///
+/// Blocking calls can't be cancelled: the worker thread running a
+/// call which timed out is abandoned and a new one is started.
+#[derive(Debug, Default)]
+pub struct DirReader {
+    timeout: Option<Duration>,
+    worker: Option<Sender<Job>>,
+}
+
+impl DirReader {
+    pub fn new(timeout: Option<Duration>) -> Self {
+        Self {
+            timeout,
+            worker: None,
+        }
+    }
+    pub fn read_dir(&mut self, path: &Path) -> io::Result<DirListing> {
+        let path = path.to_path_buf();
+        self.call(move || list_dir(&path))
+    }
+    pub fn metadata(&mut self, path: &Path) -> io::Result<Metadata> {
+        let path = path.to_path_buf();
+        self.call(move || fs::metadata(path))
+    }
+    pub fn symlink_metadata(&mut self, path: &Path) -> io::Result<Metadata> {
+        let path = path.to_path_buf();
+        self.call(move || fs::symlink_metadata(path))
+    }
+    fn call<T, F>(&mut self, f: F) -> io::Result<T>
+    where
+        T: Send + 'static,
+        F: FnOnce() -> io::Result<T> + Send + 'static,
+    {
+        let Some(timeout) = self.timeout else {
+            return f();
+        };
+        let (tx, rx) = mpsc::channel();
+        let job: Job = Box::new(move || {
+            let _ = tx.send(f());
+        });
+        let worker = self.worker.get_or_insert_with(spawn_worker);
+        if let Err(mpsc::SendError(job)) = worker.send(job) {
+            // the worker is gone, which shouldn't happen, try again with a new one
+            *worker = spawn_worker();
+            let _ = worker.send(job);
+        }
+        match rx.recv_timeout(timeout) {
+            Ok(res) => res,
+            Err(_) => {
+                self.worker = None;
+                Err(io::Error::new(
+                    io::ErrorKind::TimedOut,
+                    format!("no answer in {:?}", timeout),
+                ))
+            }
+        }
+    }
+}
+
+fn spawn_worker() -> Sender<Job> {
+    let (tx, rx) = mpsc::channel::<Job>();
+    thread::spawn(move || {
+        for job in rx {
+            job();
+        }
+    });
+    tx
+}
+
+fn list_dir(path: &Path) -> io::Result<DirListing> {
+    let mut listing = DirListing::default();
+    for entry in fs::read_dir(path)? {
+        match entry {
+            Ok(entry) => listing.entries.push(ListedEntry {
+                metadata: entry.metadata(),
+                name: entry.file_name(),
+            }),
+            Err(e) => listing.errors.push(e),
+        }
+    }
+    Ok(listing)
+}
+
+#[test]
+fn test_dir_reader_timeout() {
+    let mut reader = DirReader::new(Some(Duration::from_millis(50)));
+    let res = reader.call(|| {
+        thread::sleep(Duration::from_secs(2));
+        Ok(())
+    });
+    assert_eq!(res.unwrap_err().kind(), io::ErrorKind::TimedOut);
+    // a new worker takes over the hung one
+    let listing = reader.read_dir(Path::new(env!("CARGO_MANIFEST_DIR"))).unwrap();
+    assert!(listing.entries.iter().any(|e| e.name == "Cargo.toml"));
+}
@@ -1,9 +1,26 @@
-use std::{
-    cmp::Reverse,
-    collections::BinaryHeap,
-    fs,
-    io,
-    path::{Path, PathBuf},
+use {
+    super::{
+        ages::AgeBreakdown,
+        artifacts::{Artifact, ArtifactKind},
+        duplicates::DuplicateCandidates,
+        errors::ScanErrors,
+        index::{DirStamp, IndexedDir, ScanIndex},
+        owners::OwnerBreakdown,
+        progress::ScanProgress,
+        reader::DirReader,
+        tree::{DirNode, FileLeaf, HardLink},
+        types::TypeBreakdown,
+    },
+    std::{
+        cmp::Reverse,
+        collections::BinaryHeap,
+        ffi::{OsStr, OsString},
+        fs::Metadata,
+        os::unix::fs::MetadataExt,
+        io,
+        path::{Path, PathBuf},
+        time::{Duration, SystemTime},
+    },
 };
 
 #[derive(Debug, Clone, Eq, PartialEq)]
@@ -11,6 +28,12 @@ pub struct DirectoryEntry {
     pub path: PathBuf,
     pub size: u64,
     pub is_immediate_child: bool,
+    /// depth relative to the scanned directory (1 for its children)
+    pub depth: usize,
+    /// number of files counted in this directory and its subdirectories
+    pub file_count: u64,
+    /// most recent modification time of the files counted in this directory
+    pub modified: Option<SystemTime>,
 }
 
 impl Ord for DirectoryEntry {
@@ -25,11 +48,181 @@ impl PartialOrd for DirectoryEntry {
     }
 }
 
+/// A single regular file, candidate for the "largest files" list
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct FileEntry {
+    pub path: PathBuf,
+    pub size: u64,
+    pub modified: Option<SystemTime>,
+}
+
+impl Ord for FileEntry {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.size.cmp(&other.size)
+    }
+}
+
+impl PartialOrd for FileEntry {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// What a scan produces, biggest entries first
+#[derive(Debug, Clone, Default)]
+pub struct ScanResult {
+    /// bytes counted in the whole scanned tree
+    pub total_size: u64,
+    /// files counted in the whole scanned tree
+    pub total_files: u64,
+    pub directories: Vec<DirectoryEntry>,
+    /// empty unless the scanner was asked to track files
+    pub files: Vec<FileEntry>,
+    /// only computed when the scanner was asked to track types
+    pub types: Option<TypeBreakdown>,
+    /// only computed when the scanner was asked to track ages
+    pub ages: Option<AgeBreakdown>,
+    /// only computed when the scanner was asked to track owners
+    pub owners: Option<OwnerBreakdown>,
+    /// only gathered when the scanner was asked to track duplicates
+    pub duplicates: Option<DuplicateCandidates>,
+    /// the scanned hierarchy, only built when the scanner was asked to
+    pub tree: Option<DirNode>,
+    /// the updated index, only when the scanner was given one
+    pub index: Option<ScanIndex>,
+    /// number of directories whose content was taken from the given index
+    pub reused_dirs: u64,
+    /// what couldn't be read, and so isn't counted in the totals
+    pub errors: ScanErrors,
+    /// caches and build artifacts, biggest first, only found when the scanner was asked to
+    pub artifacts: Option<Vec<Artifact>>,
+}
+
+impl ScanResult {
+    /// Rebuild the result of a scan from a complete tree holding its files,
+    /// eg an imported one, listing the directories that a scan limited
+    /// to `max_depth` would have listed
+    pub fn from_tree(
+        tree: DirNode,
+        max_depth: Option<usize>,
+        max_results: usize,
+        max_files: usize,
+        min_size: u64,
+        track_types: bool,
+    ) -> Self {
+        let mut dirs = BinaryHeap::new();
+        let mut files = BinaryHeap::new();
+        let mut types = track_types.then(TypeBreakdown::default);
+        let mut stack = vec![(&tree, PathBuf::from(&tree.name), 0)];
+        while let Some((node, path, depth)) = stack.pop() {
+            for file in &node.files {
+                let file_path = path.join(&file.name);
+                if let Some(types) = types.as_mut() {
+                    types.add(&file_path, file.size);
+                }
+                if max_files > 0 && file.size >= min_size {
+                    let entry = FileEntry {
+                        path: file_path,
+                        size: file.size,
+                        modified: file.modified,
+                    };
+                    push_bounded(&mut files, entry, max_files);
+                }
+            }
+            // like in a scan, directories are listed one level below the depth limit
+            if max_depth.is_some_and(|max| depth > max) {
+                continue;
+            }
+            for child in &node.children {
+                let child_path = path.join(&child.name);
+                let entry = DirectoryEntry {
+                    path: child_path.clone(),
+                    size: child.size,
+                    is_immediate_child: depth == 0,
+                    depth: depth + 1,
+                    file_count: child.file_count,
+                    modified: child.modified,
+                };
+                if child.size >= min_size {
+                    push_bounded(&mut dirs, entry, max_results);
+                }
+                stack.push((child, child_path, depth + 1));
+            }
+        }
+        let mut directories: Vec<DirectoryEntry> = dirs.into_iter().map(|r| r.0).collect();
+        directories.sort_by_key(|e| Reverse(e.size));
+        let mut files: Vec<FileEntry> = files.into_iter().map(|r| r.0).collect();
+        files.sort_by_key(|e| Reverse(e.size));
+        Self {
+            total_size: tree.size,
+            total_files: tree.file_count,
+            directories,
+            files,
+            types,
+            tree: Some(tree),
+            ..Default::default()
+        }
+    }
+}
+
+/// Mutable state accumulated during a scan
+struct ScanState {
+    now: SystemTime,
+    dirs: BinaryHeap<Reverse<DirectoryEntry>>,
+    files: BinaryHeap<Reverse<FileEntry>>,
+    types: Option<TypeBreakdown>,
+    ages: Option<AgeBreakdown>,
+    owners: Option<OwnerBreakdown>,
+    duplicates: Option<DuplicateCandidates>,
+    index: Option<ScanIndex>,
+    reused_dirs: u64,
+    errors: ScanErrors,
+    progress: Option<ScanProgress>,
+    reader: DirReader,
+    artifacts: Option<Vec<Artifact>>,
+}
+
+/// Size and freshness of the counted content of a directory
+#[derive(Debug, Clone, Copy, Default)]
+struct DirTotals {
+    size: u64,
+    files: u64,
+    modified: Option<SystemTime>,
+    /// most recent modification time, caches and build artifacts excluded
+    source_modified: Option<SystemTime>,
+}
+
+impl DirTotals {
+    fn add(&mut self, other: Self) {
+        self.size += other.size;
+        self.files += other.files;
+        self.modified = self.modified.max(other.modified);
+        self.source_modified = self.source_modified.max(other.source_modified);
+    }
+}
+
 pub struct DirectoryScanner {
     base_path: PathBuf,
     max_depth: Option<usize>,
     max_results: usize,
+    max_files: usize,
+    min_size: u64,
     show_all: bool,
+    track_types: bool,
+    track_ages: bool,
+    track_duplicates: bool,
+    track_artifacts: bool,
+    track_owners: bool,
+    owner_dirs_depth: Option<usize>,
+    older_than: Option<Duration>,
+    newer_than: Option<Duration>,
+    build_tree: bool,
+    tree_depth: Option<usize>,
+    tree_files: bool,
+    progress: bool,
+    expected_entries: Option<u64>,
+    timeout: Option<Duration>,
+    index: Option<ScanIndex>,
 }
 
 impl DirectoryScanner {
@@ -38,7 +231,24 @@ impl DirectoryScanner {
             base_path: path.to_path_buf(),
             max_depth: Some(1),
             max_results: 20,
+            max_files: 0,
+            min_size: 0,
             show_all: false,
+            track_types: false,
+            track_ages: false,
+            track_duplicates: false,
+            track_artifacts: false,
+            track_owners: false,
+            owner_dirs_depth: None,
+            older_than: None,
+            newer_than: None,
+            build_tree: false,
+            tree_depth: None,
+            tree_files: false,
+            progress: false,
+            expected_entries: None,
+            timeout: None,
+            index: None,
         }
     }
 
@@ -52,95 +262,591 @@ impl DirectoryScanner {
         self
     }
 
+    /// Keep the `max_results` biggest directories (20 by default)
+    pub fn max_results(mut self, max_results: usize) -> Self {
+        self.max_results = max_results;
+        self
+    }
+
+    /// Don't list the directories and files smaller than `min_size` bytes
+    /// (they're still counted in their ancestors)
+    pub fn min_size(mut self, min_size: u64) -> Self {
+        self.min_size = min_size;
+        self
+    }
+
     pub fn show_all(mut self, show_all: bool) -> Self {
         self.show_all = show_all;
         self
     }
 
-    pub fn scan(&self) -> io::Result<Vec<DirectoryEntry>> {
-        let mut heap: BinaryHeap<Reverse<DirectoryEntry>> = BinaryHeap::new();
-        let mut scan_count = 0u64;
+    /// Also keep the `max_files` largest individual files (0 to disable)
+    pub fn max_files(mut self, max_files: usize) -> Self {
+        self.max_files = max_files;
+        self
+    }
+
+    /// Also aggregate the scanned files by extension and category
+    pub fn track_types(mut self, track_types: bool) -> Self {
+        self.track_types = track_types;
+        self
+    }
+
+    /// Also bucket the scanned bytes by modification and access age
+    pub fn track_ages(mut self, track_ages: bool) -> Self {
+        self.track_ages = track_ages;
+        self
+    }
+
+    /// Also gather the files which may be duplicates of other ones
+    pub fn track_duplicates(mut self, track_duplicates: bool) -> Self {
+        self.track_duplicates = track_duplicates;
+        self
+    }
+
+    /// Also find the caches and build artifacts, looking into hidden
+    /// directories, and measure them
+    pub fn track_artifacts(mut self, track_artifacts: bool) -> Self {
+        self.track_artifacts = track_artifacts;
+        self
+    }
+
+    /// Also sum the scanned bytes by owning user and group
+    pub fn track_owners(mut self, track_owners: bool) -> Self {
+        self.track_owners = track_owners;
+        self
+    }
+
+    /// When tracking owners, also sum their bytes by directory, those
+    /// directories being at most `depth` levels below the scanned one
+    pub fn owner_dirs_depth(mut self, depth: Option<usize>) -> Self {
+        self.owner_dirs_depth = depth;
+        self
+    }
+
+    /// Only count files last modified more than `age` ago
+    pub fn older_than(mut self, age: Option<Duration>) -> Self {
+        self.older_than = age;
+        self
+    }
+
+    /// Only count files last modified less than `age` ago
+    pub fn newer_than(mut self, age: Option<Duration>) -> Self {
+        self.newer_than = age;
+        self
+    }
+
+    /// Also keep the hierarchy of scanned directories, down to `max_depth`
+    /// (sizes of deeper directories are still counted in their ancestors)
+    pub fn build_tree(mut self, max_depth: Option<usize>) -> Self {
+        self.build_tree = true;
+        self.tree_depth = max_depth;
+        self
+    }
+
+    /// Also keep the counted files in the nodes of the tree
+    pub fn tree_files(mut self, tree_files: bool) -> Self {
+        self.tree_files = tree_files;
+        self
+    }
+
+    /// Whether to report the progress of the scan on stderr
+    pub fn progress(mut self, progress: bool) -> Self {
+        self.progress = progress;
+        self
+    }
+
+    /// How many entries the scan should meet, to estimate its remaining time
+    pub fn expected_entries(mut self, expected_entries: Option<u64>) -> Self {
+        self.expected_entries = expected_entries;
+        self
+    }
+
+    /// Give up on directories whose reading takes longer than `timeout`,
+    /// as happens on hung network mounts, and count them as errors
+    pub fn timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Reuse the content of the directories which didn't change since `index`
+    /// was built, and return an updated index in the result, unless the scan
+    /// is limited in depth.
+    ///
+    /// The index only holds directory totals, so it must not be given when
+    /// files are tracked or filtered by age.
+    pub fn index(mut self, index: Option<ScanIndex>) -> Self {
+        self.index = index;
+        self
+    }
+
+    pub fn scan(&self) -> io::Result<ScanResult> {
+        let mut state = ScanState {
+            now: SystemTime::now(),
+            dirs: BinaryHeap::new(),
+            files: BinaryHeap::new(),
+            types: self.track_types.then(TypeBreakdown::default),
+            ages: self.track_ages.then(AgeBreakdown::default),
+            owners: self
+                .track_owners
+                .then(|| OwnerBreakdown::new(&self.base_path, self.owner_dirs_depth)),
+            duplicates: self.track_duplicates.then(DuplicateCandidates::default),
+            // a depth limited scan doesn't visit everything, so it can use the
+            // index but doesn't rebuild it
+            index: (self.index.is_some() && self.max_depth.is_none())
+                .then(|| ScanIndex::new(&self.base_path)),
+            reused_dirs: 0,
+            errors: ScanErrors::default(),
+            progress: self.progress.then(|| ScanProgress::new(self.expected_entries)),
+            reader: DirReader::new(self.timeout),
+            artifacts: self.track_artifacts.then(Vec::new),
+        };
+
+        let mut tree = self
+            .build_tree
+            .then(|| DirNode::new(self.base_path.as_os_str().to_os_string()));
 
         // Single-pass: calculate size while traversing
-        self.scan_single_pass(&self.base_path, 0, &mut heap, &mut scan_count)?;
+        let totals = self.scan_single_pass(&self.base_path, 0, &mut state, tree.as_mut())?;
+        if let Some(tree) = tree.as_mut() {
+            tree.set_totals(totals.size, totals.files, totals.modified);
+        }
 
-        // Clear progress line
-        eprint!("\r                                                    \r");
+        if let Some(progress) = state.progress.as_mut() {
+            progress.finish();
+        }
 
         // Extract and sort
-        let mut results: Vec<DirectoryEntry> = heap.into_iter().map(|r| r.0).collect();
-        results.sort_by(|a, b| b.size.cmp(&a.size));
+        let mut directories: Vec<DirectoryEntry> = state.dirs.into_iter().map(|r| r.0).collect();
+        directories.sort_by_key(|e| Reverse(e.size));
+        let mut files: Vec<FileEntry> = state.files.into_iter().map(|r| r.0).collect();
+        files.sort_by_key(|e| Reverse(e.size));
 
-        Ok(results)
+        Ok(ScanResult {
+            total_size: totals.size,
+            total_files: totals.files,
+            directories,
+            files,
+            types: state.types,
+            ages: state.ages,
+            owners: state.owners,
+            duplicates: state.duplicates,
+            tree,
+            index: state.index,
+            reused_dirs: state.reused_dirs,
+            errors: state.errors,
+            artifacts: state.artifacts.map(|mut artifacts| {
+                artifacts.sort_by_key(|a| Reverse(a.size));
+                artifacts
+            }),
+        })
     }
 
     fn scan_single_pass(
         &self,
         path: &Path,
         current_depth: usize,
-        heap: &mut BinaryHeap<Reverse<DirectoryEntry>>,
-        scan_count: &mut u64,
-    ) -> io::Result<u64> {
-        // Show progress every 500 directories
-        *scan_count += 1;
-        if *scan_count % 500 == 0 {
-            eprint!("\rScanning... {} directories", scan_count);
-            use std::io::Write;
-            std::io::stderr().flush().ok();
-        }
-
-        let mut total_size = 0u64;
-
-        let entries = match fs::read_dir(path) {
-            Ok(e) => e,
-            Err(_) => return Ok(0),
+        state: &mut ScanState,
+        mut node: Option<&mut DirNode>,
+    ) -> io::Result<DirTotals> {
+        if let Some(progress) = state.progress.as_mut() {
+            progress.add_dir();
+        }
+
+        let stamp = if self.index.is_some() {
+            match state.reader.metadata(path) {
+                Ok(m) => Some(DirStamp::of(&m)),
+                Err(e) if e.kind() == io::ErrorKind::TimedOut => {
+                    state.errors.add(path, &e, true);
+                    return Ok(DirTotals::default());
+                }
+                Err(_) => None,
+            }
+        } else {
+            None
+        };
+
+        // An unchanged directory still has the same files and subdirectories,
+        // but the content of the subdirectories must still be checked
+        let indexed = self
+            .index
+            .as_ref()
+            .and_then(|index| index.get(path))
+            .filter(|indexed| Some(indexed.stamp) == stamp);
+        if let Some(indexed) = indexed {
+            state.reused_dirs += 1;
+            if let Some(progress) = state.progress.as_mut() {
+                progress.add_files(indexed.own_files, indexed.own_size);
+            }
+            let own = DirTotals {
+                size: indexed.own_size,
+                files: indexed.own_files,
+                modified: indexed.own_modified,
+                source_modified: indexed.own_modified,
+            };
+            let mut totals = own;
+            if let Some(node) = node.as_deref_mut() {
+                node.own_size += own.size;
+            }
+            for name in &indexed.subdirs {
+                let subdir_path = path.join(name);
+                match state.reader.symlink_metadata(&subdir_path) {
+                    Ok(m) if m.is_dir() => {
+                        totals.add(self.visit_dir(subdir_path, name, current_depth, state, node.as_deref_mut())?);
+                    }
+                    Ok(_) => {}
+                    Err(e) => state.errors.add(&subdir_path, &e, true),
+                }
+            }
+            self.record(path, stamp, own, indexed.subdirs.clone(), state);
+            return Ok(totals);
+        }
+
+        let mut totals = DirTotals::default();
+        let mut own = DirTotals::default();
+        let mut subdirs = Vec::new();
+        let mut complete = true;
+
+        let listing = match state.reader.read_dir(path) {
+            Ok(listing) => listing,
+            Err(e) => {
+                state.errors.add(path, &e, true);
+                return Ok(totals);
+            }
         };
+        for e in &listing.errors {
+            state.errors.add(path, e, false);
+            complete = false;
+        }
 
-        for entry in entries.flatten() {
-            let entry_path = entry.path();
+        let first_artifact = state.artifacts.as_ref().map_or(0, Vec::len);
+        for entry in listing.entries {
+            let entry_path = path.join(&entry.name);
             
-            // Filter out unwanted directories (unless --all is specified)
-            if !self.show_all && should_skip(&entry_path) {
+            // Filter out unwanted directories (unless --all is specified),
+            // or look everywhere for artifacts
+            if !self.show_all && !self.track_artifacts && should_skip(&entry_path) {
+                if let Some(node) = node.as_deref_mut().filter(|_| self.tree_files) {
+                    node.excluded.push(entry.name);
+                }
                 continue;
             }
 
-            if let Ok(metadata) = entry.metadata() {
-                if metadata.is_file() {
-                    total_size += metadata.len();
-                } else if metadata.is_dir() {
-                    // Recurse within depth limit
-                    let should_recurse = self.max_depth.map_or(true, |max| current_depth < max);
-                    
-                    let subdir_size = if should_recurse {
-                        self.scan_single_pass(&entry_path, current_depth + 1, heap, scan_count)?
-                    } else {
-                        // Even if we don't recurse, calculate size for this directory
-                        calculate_size_no_recurse(&entry_path)
-                    };
+            let metadata = match entry.metadata {
+                Ok(metadata) => metadata,
+                Err(e) => {
+                    state.errors.add(&entry_path, &e, false);
+                    complete = false;
+                    continue;
+                }
+            };
+            if self.track_artifacts && metadata.is_dir() {
+                if let Some(kind) = ArtifactKind::of_dir(&entry_path) {
+                    let mut artifact = Artifact::new(entry_path, kind);
+                    // the artifact is in the tree like any directory, as it counts in its totals
+                    let keep_child = node.is_some()
+                        && self.tree_depth.map_or(true, |max| current_depth < max);
+                    let mut child = keep_child.then(|| DirNode::new(entry.name));
+                    let subtree =
+                        self.subtree_totals(&artifact.path, current_depth + 1, state, child.as_mut());
+                    if let (Some(node), Some(mut child)) = (node.as_deref_mut(), child) {
+                        child.set_totals(subtree.size, subtree.files, subtree.modified);
+                        node.children.push(child);
+                    }
+                    artifact.size = subtree.size;
+                    artifact.files = subtree.files;
+                    artifact.modified = subtree.modified;
+                    totals.add(subtree);
+                    if let Some(artifacts) = state.artifacts.as_mut() {
+                        artifacts.push(artifact);
+                    }
+                    continue;
+                }
+            }
+            if metadata.is_file() {
+                if let Some(file) = self.visit_file(entry_path, &metadata, state) {
+                    totals.add(file);
+                    own.add(file);
+                    if let Some(node) = node.as_deref_mut() {
+                        node.own_size += file.size;
+                        if self.tree_files {
+                            node.files.push(file_leaf(entry.name, &metadata, file));
+                        }
+                    }
+                }
+            } else if metadata.is_dir() {
+                let name = entry.name;
+                totals.add(self.visit_dir(entry_path, &name, current_depth, state, node.as_deref_mut())?);
+                if stamp.is_some() {
+                    subdirs.push(name);
+                }
+            }
+        }
+
+        // the artifacts found below belong to this directory when it's their project
+        if let Some(artifacts) = state.artifacts.as_mut() {
+            for artifact in &mut artifacts[first_artifact..] {
+                if artifact.project == path {
+                    artifact.project_modified = totals.source_modified;
+                }
+            }
+        }
+
+        // a directory which wasn't completely read must be read again next time
+        if complete {
+            self.record(path, stamp, own, subdirs, state);
+        }
+        Ok(totals)
+    }
+
+    /// Scan a subdirectory found at `current_depth`, keeping it in the
+    /// tree and in the biggest directories when relevant
+    fn visit_dir(
+        &self,
+        path: PathBuf,
+        name: &OsStr,
+        current_depth: usize,
+        state: &mut ScanState,
+        node: Option<&mut DirNode>,
+    ) -> io::Result<DirTotals> {
+        // Recurse within depth limit
+        let should_recurse = self.max_depth.map_or(true, |max| current_depth < max);
+
+        // Keep a node for the subdirectory when within the tree depth
+        let keep_child =
+            node.is_some() && self.tree_depth.map_or(true, |max| current_depth < max);
+        let mut child = keep_child.then(|| DirNode::new(name.to_os_string()));
+
+        let subdir = if should_recurse {
+            self.scan_single_pass(&path, current_depth + 1, state, child.as_mut())?
+        } else {
+            // Even if we don't recurse, calculate size for this directory
+            self.calculate_size_no_recurse(&path, state)
+        };
+
+        if let (Some(node), Some(mut child)) = (node, child) {
+            child.set_totals(subdir.size, subdir.files, subdir.modified);
+            node.children.push(child);
+        }
+
+        // Add to heap if it's a candidate
+        let is_immediate = current_depth == 0;
+        let dir_entry = DirectoryEntry {
+            path,
+            size: subdir.size,
+            is_immediate_child: is_immediate,
+            depth: current_depth + 1,
+            file_count: subdir.files,
+            modified: subdir.modified,
+        };
+        if subdir.size >= self.min_size {
+            push_bounded(&mut state.dirs, dir_entry, self.max_results);
+        }
+
+        Ok(subdir)
+    }
+
+    /// Store what was found in a directory in the index being built, if any
+    fn record(
+        &self,
+        path: &Path,
+        stamp: Option<DirStamp>,
+        own: DirTotals,
+        subdirs: Vec<OsString>,
+        state: &mut ScanState,
+    ) {
+        if let (Some(index), Some(stamp)) = (state.index.as_mut(), stamp) {
+            index.insert(
+                path.to_path_buf(),
+                IndexedDir {
+                    stamp,
+                    own_size: own.size,
+                    own_files: own.files,
+                    own_modified: own.modified,
+                    subdirs,
+                },
+            );
+        }
+    }
 
-                    total_size += subdir_size;
+    /// Account for a regular file, unless it's filtered out by its age.
+    ///
+    /// Return what the file adds to its directory, or `None` when it doesn't count
+    fn visit_file(
+        &self,
+        path: PathBuf,
+        metadata: &Metadata,
+        state: &mut ScanState,
+    ) -> Option<DirTotals> {
+        if let Some(progress) = state.progress.as_mut() {
+            progress.add_files(1, metadata.len());
+        }
+        let modified = metadata.modified().ok();
+        if self.older_than.is_some() || self.newer_than.is_some() {
+            let age = age_at(state.now, modified?);
+            if self.older_than.is_some_and(|min| age < min) {
+                return None;
+            }
+            if self.newer_than.is_some_and(|max| age >= max) {
+                return None;
+            }
+        }
+        let size = metadata.len();
+        if let Some(ages) = state.ages.as_mut() {
+            let accessed = metadata.accessed().ok();
+            ages.add(
+                size,
+                modified.map(|t| age_at(state.now, t)),
+                accessed.map(|t| age_at(state.now, t)),
+            );
+        }
+        if let Some(owners) = state.owners.as_mut() {
+            owners.add(&path, metadata.uid(), metadata.gid(), size);
+        }
+        if let Some(types) = state.types.as_mut() {
+            types.add(&path, size);
+        }
+        if let Some(duplicates) = state.duplicates.as_mut() {
+            duplicates.add(path.clone(), metadata);
+        }
+        if self.max_files > 0 && size >= self.min_size {
+            push_bounded(&mut state.files, FileEntry { path, size, modified }, self.max_files);
+        }
+        Some(DirTotals {
+            size,
+            files: 1,
+            modified,
+            source_modified: modified,
+        })
+    }
 
-                    // Add to heap if it's a candidate
-                    let is_immediate = current_depth == 0;
-                    let dir_entry = DirectoryEntry {
-                        path: entry_path,
-                        size: subdir_size,
-                        is_immediate_child: is_immediate,
+    /// Measure a whole subtree, without skipping anything, for a cache or
+    /// build artifact, filling its `node` when the tree is built
+    fn subtree_totals(
+        &self,
+        path: &Path,
+        current_depth: usize,
+        state: &mut ScanState,
+        mut node: Option<&mut DirNode>,
+    ) -> DirTotals {
+        if let Some(progress) = state.progress.as_mut() {
+            progress.add_dir();
+        }
+        let mut totals = DirTotals::default();
+        let listing = match state.reader.read_dir(path) {
+            Ok(listing) => listing,
+            Err(e) => {
+                state.errors.add(path, &e, true);
+                return totals;
+            }
+        };
+        for e in &listing.errors {
+            state.errors.add(path, e, false);
+        }
+        for entry in listing.entries {
+            let entry_path = path.join(&entry.name);
+            match entry.metadata {
+                Ok(metadata) if metadata.is_file() => {
+                    if let Some(progress) = state.progress.as_mut() {
+                        progress.add_files(1, metadata.len());
+                    }
+                    let file = DirTotals {
+                        size: metadata.len(),
+                        files: 1,
+                        modified: metadata.modified().ok(),
+                        source_modified: None,
                     };
+                    totals.add(file);
+                    if let Some(node) = node.as_deref_mut() {
+                        node.own_size += file.size;
+                        if self.tree_files {
+                            node.files.push(file_leaf(entry.name, &metadata, file));
+                        }
+                    }
+                }
+                Ok(metadata) if metadata.is_dir() => {
+                    let keep_child = node.is_some()
+                        && self.tree_depth.map_or(true, |max| current_depth < max);
+                    let mut child = keep_child.then(|| DirNode::new(entry.name));
+                    let subdir = self.subtree_totals(&entry_path, current_depth + 1, state, child.as_mut());
+                    if let (Some(node), Some(mut child)) = (node.as_deref_mut(), child) {
+                        child.set_totals(subdir.size, subdir.files, subdir.modified);
+                        node.children.push(child);
+                    }
+                    totals.add(subdir);
+                }
+                Ok(_) => {}
+                Err(e) => state.errors.add(&entry_path, &e, false),
+            }
+        }
+        totals
+    }
 
-                    if heap.len() < self.max_results {
-                        heap.push(Reverse(dir_entry));
-                    } else if let Some(Reverse(smallest)) = heap.peek() {
-                        if subdir_size > smallest.size {
-                            heap.pop();
-                            heap.push(Reverse(dir_entry));
+    /// Calculate size without recursing into subdirectories
+    fn calculate_size_no_recurse(&self, path: &Path, state: &mut ScanState) -> DirTotals {
+        let mut totals = DirTotals::default();
+
+        let listing = match state.reader.read_dir(path) {
+            Ok(listing) => listing,
+            Err(e) => {
+                state.errors.add(path, &e, true);
+                return totals;
+            }
+        };
+        for e in &listing.errors {
+            state.errors.add(path, e, false);
+        }
+        for entry in listing.entries {
+            let entry_path = path.join(&entry.name);
+            match entry.metadata {
+                Ok(metadata) => {
+                    if metadata.is_file() {
+                        if let Some(file) = self.visit_file(entry_path, &metadata, state) {
+                            totals.add(file);
                         }
                     }
+                    // Don't recurse into subdirectories
                 }
+                Err(e) => state.errors.add(&entry_path, &e, false),
             }
         }
 
-        Ok(total_size)
+        totals
+    }
+}
+
+/// The leaf of a counted file in the tree
+fn file_leaf(
+    name: OsString,
+    metadata: &Metadata,
+    file: DirTotals,
+) -> FileLeaf {
+    FileLeaf {
+        name,
+        size: file.size,
+        modified: file.modified,
+        link: (metadata.nlink() > 1).then(|| HardLink {
+            ino: metadata.ino(),
+            nlink: metadata.nlink(),
+        }),
+    }
+}
+
+/// Age of a file at `now`, files dated in the future being considered new
+fn age_at(now: SystemTime, time: SystemTime) -> Duration {
+    now.duration_since(time).unwrap_or_default()
+}
+
+/// Push an item into a min-heap holding at most `max` items, so
+/// that only the biggest ones are kept
+fn push_bounded<T: Ord>(heap: &mut BinaryHeap<Reverse<T>>, item: T, max: usize) {
+    if heap.len() < max {
+        heap.push(Reverse(item));
+    } else if let Some(Reverse(smallest)) = heap.peek() {
+        if item > *smallest {
+            heap.pop();
+            heap.push(Reverse(item));
+        }
     }
 }
 
@@ -178,20 +884,13 @@ fn should_skip(path: &Path) -> bool {
     }
 }
 
-/// Calculate size without recursing into subdirectories
-fn calculate_size_no_recurse(path: &Path) -> u64 {
-    let mut total = 0u64;
-
-    if let Ok(entries) = fs::read_dir(path) {
-        for entry in entries.flatten() {
-            if let Ok(metadata) = entry.metadata() {
-                if metadata.is_file() {
-                    total += metadata.len();
-                }
-                // Don't recurse into subdirectories
-            }
-        }
+#[test]
+fn test_push_bounded() {
+    let mut heap = BinaryHeap::new();
+    for n in [5, 1, 9, 3, 7, 2] {
+        push_bounded(&mut heap, n, 3);
     }
-
-    total
+    let mut kept: Vec<u32> = heap.into_iter().map(|r| r.0).collect();
+    kept.sort();
+    assert_eq!(kept, vec![5, 7, 9]);
 }
@@ -0,0 +1,229 @@
+use {
+    crate::{
+        Args,
+        directory::{
+            display::{format_bytes, make_skin},
+            output::insert_path,
+        },
+        process::graph::render_bar_compact,
+    },
+    serde_json::{Map, Value},
+    std::{
+        ffi::OsString,
+        io::{self, Write},
+        path::Path,
+        time::SystemTime,
+    },
+    termimad::minimad::{OwningTemplateExpander, TableBuilder, Col, Alignment},
+};
+
+/// A directory in the scanned hierarchy
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DirNode {
+    /// file name, or full path for the root of the scan
+    pub name: OsString,
+    /// inclusive size: own files and all subdirectories
+    pub size: u64,
+    /// exclusive size: files directly in this directory
+    pub own_size: u64,
+    /// number of files in this directory and its subdirectories
+    pub file_count: u64,
+    pub modified: Option<SystemTime>,
+    pub children: Vec<DirNode>,
+    /// files directly in this directory, only kept when the scanner was asked to
+    pub files: Vec<FileLeaf>,
+    /// entries of this directory left out of the scan, eg hidden and build
+    /// directories, only kept with the files
+    pub excluded: Vec<OsString>,
+}
+
+/// A file in the scanned hierarchy
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileLeaf {
+    pub name: OsString,
+    pub size: u64,
+    pub modified: Option<SystemTime>,
+    /// only for files with several hard links
+    pub link: Option<HardLink>,
+}
+
+/// What identifies the hard links of a same file, so that
+/// ncdu doesn't count its size several times
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HardLink {
+    pub ino: u64,
+    pub nlink: u64,
+}
+
+impl DirNode {
+    pub fn new(name: OsString) -> Self {
+        Self {
+            name,
+            size: 0,
+            own_size: 0,
+            file_count: 0,
+            modified: None,
+            children: Vec::new(),
+            files: Vec::new(),
+            excluded: Vec::new(),
+        }
+    }
+    pub(crate) fn set_totals(
+        &mut self,
+        size: u64,
+        file_count: u64,
+        modified: Option<SystemTime>,
+    ) {
+        self.size = size;
+        self.file_count = file_count;
+        self.modified = modified;
+    }
+    /// Drop the subdirectories deeper than `depth` below this node
+    pub(crate) fn prune(&mut self, depth: usize) {
+        if depth == 0 {
+            self.children.clear();
+        }
+        for child in &mut self.children {
+            child.prune(depth - 1);
+        }
+    }
+    /// Sort children, recursively, biggest first
+    pub fn sort_by_size(&mut self) {
+        self.children
+            .sort_by(|a, b| b.size.cmp(&a.size).then_with(|| a.name.cmp(&b.name)));
+        for child in &mut self.children {
+            child.sort_by_size();
+        }
+    }
+    pub fn to_json(&self) -> Value {
+        let mut obj = Map::new();
+        insert_path(&mut obj, "name", Path::new(&self.name));
+        obj.insert("size".into(), self.size.into());
+        obj.insert("own-size".into(), self.own_size.into());
+        obj.insert("files".into(), self.file_count.into());
+        obj.insert(
+            "children".into(),
+            Value::Array(self.children.iter().map(|c| c.to_json()).collect()),
+        );
+        Value::Object(obj)
+    }
+}
+
+/// A line of the rendered tree
+struct TreeLine {
+    label: String,
+    size: u64,
+    own_size: Option<u64>,
+    share: f32,
+}
+
+fn share_pct(part: u64, total: u64) -> f32 {
+    if total == 0 {
+        0.0
+    } else {
+        100.0 * part as f32 / total as f32
+    }
+}
+
+/// Compute the lines of the subtree below `node`, collapsing the children
+/// weighing less than `min_percent` of their parent into a "(N others)" line
+fn collect_lines(
+    node: &DirNode,
+    prefix: &str,
+    min_percent: f32,
+    ascii: bool,
+    lines: &mut Vec<TreeLine>,
+) {
+    let (shown, others): (Vec<&DirNode>, Vec<&DirNode>) = node
+        .children
+        .iter()
+        .partition(|c| share_pct(c.size, node.size) >= min_percent);
+    let (branch, last_branch, pipe) = if ascii {
+        ("|-- ", "`-- ", "|   ")
+    } else {
+        ("├── ", "└── ", "│   ")
+    };
+    let count = shown.len() + usize::from(!others.is_empty());
+    for (i, child) in shown.iter().enumerate() {
+        let is_last = i + 1 == count;
+        lines.push(TreeLine {
+            label: format!(
+                "{}{}{}",
+                prefix,
+                if is_last { last_branch } else { branch },
+                child.name.to_string_lossy(),
+            ),
+            size: child.size,
+            own_size: Some(child.own_size),
+            share: share_pct(child.size, node.size),
+        });
+        let child_prefix = format!("{}{}", prefix, if is_last { "    " } else { pipe });
+        collect_lines(child, &child_prefix, min_percent, ascii, lines);
+    }
+    if !others.is_empty() {
+        let size = others.iter().map(|c| c.size).sum();
+        lines.push(TreeLine {
+            label: format!("{}{}({} others)", prefix, last_branch, others.len()),
+            size,
+            own_size: None,
+            share: share_pct(size, node.size),
+        });
+    }
+}
+
+/// Write the tree as an indented table
+pub fn write_tree<W: Write>(
+    w: &mut W,
+    root: &DirNode,
+    min_percent: f32,
+    args: &Args,
+) -> io::Result<()> {
+    let mut lines = vec![TreeLine {
+        label: root.name.to_string_lossy().to_string(),
+        size: root.size,
+        own_size: Some(root.own_size),
+        share: 100.0,
+    }];
+    collect_lines(root, "", min_percent, args.ascii, &mut lines);
+    let mut expander = OwningTemplateExpander::new();
+    expander.set_default("");
+    for line in &lines {
+        expander
+            .sub("rows")
+            .set("name", &line.label)
+            .set("size", format_bytes(line.size))
+            .set_option("own", line.own_size.map(format_bytes))
+            .set("share", format!("{:.1}%", line.share))
+            .set_md("bar", render_bar_compact(line.share, 10, args.ascii));
+    }
+    let mut tbl = TableBuilder::default();
+    tbl.col(Col::new("Directory", "${name}").align_content(Alignment::Left))
+        .col(Col::new("Size", "${size}").align_content(Alignment::Right))
+        .col(Col::new("Own", "${own}").align_content(Alignment::Right))
+        .col(Col::new("Parent%", "${share}").align_content(Alignment::Right))
+        .col(Col::new("Usage", "${bar}").align_content(Alignment::Left));
+    writeln!(w)?;
+    make_skin(args).write_owning_expander_md(w, &expander, &tbl)
+}
+
+#[test]
+fn test_tree_lines() {
+    let leaf = |name: &str, size: u64| DirNode {
+        size,
+        own_size: size,
+        ..DirNode::new(name.into())
+    };
+    let mut root = DirNode {
+        size: 1000,
+        own_size: 100,
+        children: vec![leaf("small-a", 3), leaf("big", 880), leaf("small-b", 2), leaf("mid", 15)],
+        ..DirNode::new("/r".into())
+    };
+    root.sort_by_size();
+    let mut lines = Vec::new();
+    collect_lines(&root, "", 1.0, true, &mut lines);
+    let labels: Vec<&str> = lines.iter().map(|l| l.label.as_str()).collect();
+    assert_eq!(labels, vec!["|-- big", "|-- mid", "`-- (2 others)"]);
+    assert_eq!(lines[2].size, 5);
+    assert_eq!(lines[2].own_size, None);
+}
@@ -0,0 +1,222 @@
+use {
+    crate::{
+        Args,
+        directory::display::{format_bytes, make_skin},
+        process::graph::render_bar_compact,
+    },
+    serde_json::{Value, json},
+    std::{
+        collections::HashMap,
+        io::{self, Write},
+        path::Path,
+    },
+    termimad::minimad::{OwningTemplateExpander, TableBuilder, Col, Alignment},
+};
+
+/// Coarse family of a file, deduced from its extension
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FileCategory {
+    Video,
+    Image,
+    Archive,
+    Log,
+    VmDisk,
+    Database,
+    Source,
+    Other,
+}
+
+impl FileCategory {
+    /// Determine the category of a file from its (lowercase) extension
+    pub fn of_extension(ext: &str) -> Self {
+        match ext {
+            "mp4" | "mkv" | "avi" | "mov" | "wmv" | "webm" | "flv" | "m4v" | "mpg" | "mpeg"
+            | "vob" => Self::Video,
+            "jpg" | "jpeg" | "png" | "gif" | "bmp" | "tif" | "tiff" | "webp" | "heic" | "svg"
+            | "raw" | "cr2" | "nef" | "psd" | "xcf" | "ico" => Self::Image,
+            "zip" | "tar" | "gz" | "tgz" | "bz2" | "xz" | "zst" | "7z" | "rar" | "lz4"
+            | "lzma" | "deb" | "rpm" | "jar" | "war" | "whl" | "crate" => Self::Archive,
+            "log" | "journal" | "out" | "err" => Self::Log,
+            "qcow" | "qcow2" | "vmdk" | "vdi" | "vhd" | "vhdx" | "img" | "iso" | "ova" | "ovf" => {
+                Self::VmDisk
+            }
+            "db" | "sqlite" | "sqlite3" | "mdb" | "accdb" | "frm" | "ibd" | "myd" | "myi"
+            | "dbf" | "wal" | "ldb" | "rdb" | "aof" => Self::Database,
+            "rs" | "c" | "h" | "cpp" | "cc" | "hpp" | "py" | "js" | "jsx" | "ts" | "tsx" | "java"
+            | "kt" | "go" | "rb" | "php" | "cs" | "swift" | "scala" | "sh" | "pl" | "lua"
+            | "hs" | "ml" | "ex" | "exs" | "erl" | "clj" | "dart" | "vue" | "sql" | "toml"
+            | "yaml" | "yml" | "json" | "xml" | "html" | "css" | "scss" | "md" => Self::Source,
+            _ => Self::Other,
+        }
+    }
+    /// Determine the category of a file, handling rotated logs like `syslog.2`
+    /// or `app.log.1`
+    pub fn of_path(path: &Path, ext: &str) -> Self {
+        let category = Self::of_extension(ext);
+        if category == Self::Other && ext.chars().all(|c| c.is_ascii_digit()) {
+            if let Some(stem) = path.file_stem().and_then(|s| s.to_str()) {
+                if stem.ends_with(".log") || stem == "syslog" || stem == "messages" {
+                    return Self::Log;
+                }
+            }
+        }
+        category
+    }
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Video => "video",
+            Self::Image => "image",
+            Self::Archive => "archive",
+            Self::Log => "log",
+            Self::VmDisk => "vm-disk",
+            Self::Database => "database",
+            Self::Source => "source",
+            Self::Other => "other",
+        }
+    }
+}
+
+/// Bytes and file count of a set of files
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TypeStats {
+    pub bytes: u64,
+    pub files: u64,
+}
+
+/// Scanned space, aggregated by extension and by category
+#[derive(Debug, Clone, Default)]
+pub struct TypeBreakdown {
+    pub by_extension: HashMap<String, TypeStats>,
+    pub by_category: HashMap<FileCategory, TypeStats>,
+}
+
+/// the key used for files without extension
+static NO_EXTENSION: &str = "(none)";
+
+impl TypeBreakdown {
+    pub fn add(&mut self, path: &Path, size: u64) {
+        let ext = path
+            .extension()
+            .map(|e| e.to_string_lossy().to_lowercase())
+            .unwrap_or_else(|| NO_EXTENSION.to_string());
+        let category = FileCategory::of_path(path, &ext);
+        for stats in [
+            self.by_category.entry(category).or_default(),
+            self.by_extension.entry(ext).or_default(),
+        ] {
+            stats.bytes += size;
+            stats.files += 1;
+        }
+    }
+    pub fn total(&self) -> TypeStats {
+        self.by_category.values().fold(TypeStats::default(), |acc, s| TypeStats {
+            bytes: acc.bytes + s.bytes,
+            files: acc.files + s.files,
+        })
+    }
+    /// Categories, biggest first
+    pub fn categories(&self) -> Vec<(&'static str, TypeStats)> {
+        let mut v: Vec<_> = self.by_category.iter().map(|(c, s)| (c.name(), *s)).collect();
+        v.sort_by(|a, b| b.1.bytes.cmp(&a.1.bytes).then(a.0.cmp(b.0)));
+        v
+    }
+    /// Extensions, biggest first
+    pub fn extensions(&self) -> Vec<(&str, TypeStats)> {
+        let mut v: Vec<_> = self.by_extension.iter().map(|(e, s)| (e.as_str(), *s)).collect();
+        v.sort_by(|a, b| b.1.bytes.cmp(&a.1.bytes).then(a.0.cmp(b.0)));
+        v
+    }
+    pub fn to_json(&self) -> Value {
+        let total = self.total().bytes;
+        let rows = |v: Vec<(&str, TypeStats)>, key: &str| -> Value {
+            Value::Array(
+                v.iter()
+                    .map(|(name, s)| {
+                        json!({
+                            key: name,
+                            "bytes": s.bytes,
+                            "files": s.files,
+                            "share": share(s.bytes, total),
+                        })
+                    })
+                    .collect(),
+            )
+        };
+        json!({
+            "total-bytes": total,
+            "total-files": self.total().files,
+            "categories": rows(self.categories(), "category"),
+            "extensions": rows(self.extensions(), "extension"),
+        })
+    }
+}
+
+fn share(part: u64, total: u64) -> f64 {
+    if total == 0 {
+        0.0
+    } else {
+        part as f64 / total as f64
+    }
+}
+
+/// Write the category table then the extension table
+pub fn write_types<W: Write>(
+    w: &mut W,
+    types: &TypeBreakdown,
+    limit: usize,
+    args: &Args,
+) -> io::Result<()> {
+    let total = types.total().bytes;
+    writeln!(w, "\nScanned Space by Category:\n")?;
+    write_type_table(w, "Category", &types.categories(), total, usize::MAX, args)?;
+    let extensions = types.extensions();
+    let limit = limit.min(extensions.len());
+    writeln!(w, "\nTop {} Extensions by Size:\n", limit)?;
+    write_type_table(w, "Extension", &extensions, total, limit, args)
+}
+
+fn write_type_table<W: Write>(
+    w: &mut W,
+    title: &str,
+    rows: &[(&str, TypeStats)],
+    total: u64,
+    limit: usize,
+    args: &Args,
+) -> io::Result<()> {
+    let mut expander = OwningTemplateExpander::new();
+    expander.set_default("");
+    for (name, stats) in rows.iter().take(limit) {
+        let pct = 100.0 * share(stats.bytes, total) as f32;
+        expander
+            .sub("rows")
+            .set_md("bar", render_bar_compact(pct, 10, args.ascii))
+            .set("size", format_bytes(stats.bytes))
+            .set("share", format!("{:.1}%", pct))
+            .set("files", stats.files)
+            .set("name", name);
+    }
+    let mut tbl = TableBuilder::default();
+    tbl.col(Col::new("Usage", "${bar}").align_content(Alignment::Left))
+        .col(Col::new("Size", "${size}").align_content(Alignment::Right))
+        .col(Col::new("Share", "${share}").align_content(Alignment::Right))
+        .col(Col::new("Files", "${files}").align_content(Alignment::Right))
+        .col(Col::new(title, "${name}").align_content(Alignment::Left));
+    make_skin(args).write_owning_expander_md(w, &expander, &tbl)
+}
+
+#[test]
+fn test_type_breakdown() {
+    let mut types = TypeBreakdown::default();
+    types.add(Path::new("/a/movie.MKV"), 700);
+    types.add(Path::new("/a/b/app.log.3"), 50);
+    types.add(Path::new("/a/b/app.log"), 30);
+    types.add(Path::new("/a/disk.qcow2"), 900);
+    types.add(Path::new("/a/Makefile"), 2);
+    assert_eq!(types.total(), TypeStats { bytes: 1682, files: 5 });
+    let categories = types.categories();
+    assert_eq!(categories[0], ("vm-disk", TypeStats { bytes: 900, files: 1 }));
+    assert_eq!(categories[1], ("video", TypeStats { bytes: 700, files: 1 }));
+    assert_eq!(categories[2], ("log", TypeStats { bytes: 80, files: 2 }));
+    assert_eq!(types.by_extension["mkv"].bytes, 700);
+    assert_eq!(types.by_extension[NO_EXTENSION].files, 1);
+}
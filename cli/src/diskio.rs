@@ -0,0 +1,143 @@
+use {
+    crate::sysfs::Sysfs,
+    lfs_core::Mount,
+    std::{
+        collections::HashMap,
+        fs,
+        sync::OnceLock,
+        thread,
+        time::{Duration, Instant},
+    },
+};
+
+static DISKSTATS_FILE: &str = "/proc/diskstats";
+
+/// Delay between the two readings of `/proc/diskstats`
+static SAMPLE_WINDOW: Duration = Duration::from_millis(500);
+
+/// Unit of the sector counts of `/proc/diskstats`, whatever the device
+static SECTOR_SIZE: u64 = 512;
+
+/// The cumulated counters of a device, as found in `/proc/diskstats`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DiskCounters {
+    pub reads: u64,
+    pub sectors_read: u64,
+    pub read_ms: u64,
+    pub writes: u64,
+    pub sectors_written: u64,
+    pub write_ms: u64,
+    /// time during which the device had requests in flight
+    pub io_ms: u64,
+}
+
+/// Parse the content of `/proc/diskstats`, by device name
+pub fn parse_diskstats(content: &str) -> HashMap<String, DiskCounters> {
+    let mut devices = HashMap::new();
+    for line in content.lines() {
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        if fields.len() < 14 {
+            continue;
+        }
+        let n = |i: usize| fields[i].parse::<u64>().unwrap_or(0);
+        devices.insert(
+            fields[2].to_string(),
+            DiskCounters {
+                reads: n(3),
+                sectors_read: n(5),
+                read_ms: n(6),
+                writes: n(7),
+                sectors_written: n(9),
+                write_ms: n(10),
+                io_ms: n(12),
+            },
+        );
+    }
+    devices
+}
+
+/// How busy a device was during the sample window
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DiskIo {
+    /// bytes read per second
+    pub read_rate: f64,
+    /// bytes written per second
+    pub write_rate: f64,
+    /// read and write requests per second
+    pub iops: f64,
+    /// average time of a request, queueing included, in milliseconds,
+    /// `None` when there was no request
+    pub latency: Option<f64>,
+    /// percentage of the time the device was busy
+    pub util: f64,
+}
+
+impl DiskIo {
+    pub fn between(before: DiskCounters, after: DiskCounters, elapsed: Duration) -> Self {
+        let secs = elapsed.as_secs_f64().max(0.001);
+        let delta = |a: u64, b: u64| b.saturating_sub(a);
+        let requests = delta(before.reads, after.reads) + delta(before.writes, after.writes);
+        let request_ms = delta(before.read_ms, after.read_ms) + delta(before.write_ms, after.write_ms);
+        let io_ms = delta(before.io_ms, after.io_ms) as f64;
+        Self {
+            read_rate: (delta(before.sectors_read, after.sectors_read) * SECTOR_SIZE) as f64 / secs,
+            write_rate: (delta(before.sectors_written, after.sectors_written) * SECTOR_SIZE) as f64 / secs,
+            iops: requests as f64 / secs,
+            latency: (requests > 0).then(|| request_ms as f64 / requests as f64),
+            util: (100.0 * io_ms / (secs * 1000.0)).min(100.0),
+        }
+    }
+}
+
+/// The activity of the disks, measured once, by disk name
+fn sampled() -> &'static HashMap<String, DiskIo> {
+    static SAMPLE: OnceLock<HashMap<String, DiskIo>> = OnceLock::new();
+    SAMPLE.get_or_init(|| {
+        let read = || {
+            fs::read_to_string(DISKSTATS_FILE)
+                .map(|content| parse_diskstats(&content))
+                .unwrap_or_default()
+        };
+        let start = Instant::now();
+        let before = read();
+        thread::sleep(SAMPLE_WINDOW);
+        let after = read();
+        let elapsed = start.elapsed();
+        after
+            .into_iter()
+            .filter_map(|(name, after)| {
+                let before = *before.get(&name)?;
+                Some((name, DiskIo::between(before, after, elapsed)))
+            })
+            .collect()
+    })
+}
+
+/// The activity of the disk under a mount, stacked devices like LVM
+/// volumes being resolved to their (first) physical disk.
+///
+/// The first call takes the time of the sample window
+pub fn of_mount(mount: &Mount) -> Option<DiskIo> {
+    let sysfs = Sysfs::default();
+    let name = sysfs.name_of(mount.info.dev)?;
+    sampled().get(&sysfs.physical_disk(&name)).copied()
+}
+
+#[test]
+fn test_disk_io() {
+    let before = parse_diskstats(
+        "   8       0 sda 1000 10 80000 4000 500 20 40000 3000 0 6000 7000 0 0 0 0\n\
+            8       1 sda1 900 10 70000 3500 400 20 30000 2500 0 5000 6000 0 0 0 0\n\
+         broken line\n",
+    );
+    assert_eq!(before.len(), 2);
+    let after = parse_diskstats(
+        "   8       0 sda 1100 10 84000 4500 600 20 48000 3700 0 6500 7800 0 0 0 0\n",
+    );
+    let io = DiskIo::between(before["sda"], after["sda"], Duration::from_secs(1));
+    assert_eq!(io.read_rate, 4000.0 * 512.0);
+    assert_eq!(io.write_rate, 8000.0 * 512.0);
+    assert_eq!(io.iops, 200.0);
+    assert_eq!(io.latency, Some(6.0));
+    assert_eq!(io.util, 50.0);
+}
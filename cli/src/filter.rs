@@ -1,5 +1,8 @@
 use {
-    crate::col_expr::*,
+    crate::{
+        col_expr::*,
+        history::Trends,
+    },
     bet::*,
     lfs_core::*,
     std::str::FromStr,
@@ -22,11 +25,12 @@ impl Filter {
     pub fn eval(
         &self,
         mount: &Mount,
+        trends: &Trends,
     ) -> Result<bool, EvalExprError> {
         self.expr
             .eval_faillible(
                 // leaf evaluation
-                |col_expr| col_expr.eval(mount),
+                |col_expr| col_expr.eval(mount, trends),
                 // bool operation
                 |op, a, b| match (op, b) {
                     (BoolOperator::And, Some(b)) => Ok(a & b),
@@ -48,10 +52,11 @@ impl Filter {
     pub fn filter<'m>(
         &self,
         mounts: &'m [Mount],
+        trends: &Trends,
     ) -> Result<Vec<&'m Mount>, EvalExprError> {
         let mut filtered = Vec::new();
         for mount in mounts {
-            if self.eval(mount)? {
+            if self.eval(mount, trends)? {
                 filtered.push(mount);
             }
         }
@@ -0,0 +1,193 @@
+use {
+    clap::ValueEnum,
+    lfs_core::{DeviceId, Mount},
+    serde_json::{Value, json},
+    crate::units::Units,
+};
+
+/// How the mounts are grouped in sections, with subtotals
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GroupBy {
+    /// the filesystem type, eg `ext4` or `nfs4`
+    Type,
+    /// the type of disk, eg `SSD` or `HDD`, remote mounts being together
+    Disk,
+    /// remote or local
+    Remote,
+    /// the disk, eg `nvme0n1`, or the filesystem when there's none
+    Device,
+    /// the filesystem label
+    Label,
+}
+
+impl GroupBy {
+    pub fn key(
+        self,
+        mount: &Mount,
+    ) -> String {
+        match self {
+            Self::Type => mount.info.fs_type.clone(),
+            Self::Disk => match &mount.disk {
+                Some(disk) if !disk.disk_type().is_empty() => disk.disk_type().to_string(),
+                _ if mount.is_remote() => "remote".to_string(),
+                _ => "other".to_string(),
+            },
+            Self::Remote => {
+                if mount.is_remote() { "remote" } else { "local" }.to_string()
+            }
+            Self::Device => device_name(mount),
+            Self::Label => mount.fs_label.clone().unwrap_or_else(|| "no label".to_string()),
+        }
+    }
+}
+
+#[cfg(not(windows))]
+fn device_name(mount: &Mount) -> String {
+    match &mount.disk {
+        Some(disk) if !disk.name.is_empty() => disk.name.clone(),
+        _ => mount.info.fs.clone(),
+    }
+}
+#[cfg(windows)]
+fn device_name(mount: &Mount) -> String {
+    mount.info.fs.clone()
+}
+
+/// Sums of the stats of a set of mounts, each filesystem being
+/// counted once even when it's mounted several times
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Totals {
+    pub size: u64,
+    pub used: u64,
+    pub available: u64,
+    pub inodes: u64,
+    pub iused: u64,
+    pub ifree: u64,
+    /// the number of distinct filesystems with stats
+    pub filesystems: usize,
+    /// whether some filesystems had inodes
+    pub has_inodes: bool,
+}
+
+impl Totals {
+    pub fn of(mounts: &[&Mount]) -> Self {
+        let mut totals = Self::default();
+        let mut seen: Vec<DeviceId> = Vec::new();
+        for mount in mounts {
+            let Some(stats) = mount.stats() else {
+                continue;
+            };
+            if seen.contains(&mount.info.dev) {
+                continue;
+            }
+            seen.push(mount.info.dev);
+            totals.filesystems += 1;
+            totals.size += stats.size();
+            totals.used += stats.used();
+            totals.available += stats.available();
+            if let Some(inodes) = &stats.inodes {
+                totals.inodes += inodes.files;
+                totals.iused += inodes.used();
+                totals.ifree += inodes.favail;
+                totals.has_inodes = true;
+            }
+        }
+        totals
+    }
+    /// share of the space which isn't available, computed like the
+    /// one of a single filesystem
+    pub fn use_share(&self) -> f64 {
+        if self.size == 0 {
+            0.0
+        } else {
+            (self.size - self.available) as f64 / self.size as f64
+        }
+    }
+    pub fn inodes_use_share(&self) -> f64 {
+        if self.inodes == 0 {
+            0.0
+        } else {
+            self.iused as f64 / self.inodes as f64
+        }
+    }
+    pub fn to_json(
+        &self,
+        units: Units,
+    ) -> Value {
+        let inodes = self.has_inodes.then(|| {
+            json!({
+                "files": self.inodes,
+                "used": self.iused,
+                "free": self.ifree,
+                "used-percent": format!("{:.0}%", 100.0 * self.inodes_use_share()),
+            })
+        });
+        json!({
+            "filesystems": self.filesystems,
+            "size": units.fmt(self.size),
+            "used": units.fmt(self.used),
+            "used-percent": format!("{:.0}%", 100.0 * self.use_share()),
+            "available": units.fmt(self.available),
+            "size-bytes": self.size,
+            "used-bytes": self.used,
+            "available-bytes": self.available,
+            "inodes": inodes,
+        })
+    }
+}
+
+/// The mounts sharing a key, in the order they were given
+pub struct Group<'m> {
+    pub key: String,
+    pub mounts: Vec<&'m Mount>,
+    pub totals: Totals,
+}
+
+/// Split the already sorted mounts in groups, ordered by first appearance
+pub fn group<'m>(
+    mounts: &[&'m Mount],
+    group_by: GroupBy,
+) -> Vec<Group<'m>> {
+    let mut groups: Vec<Group> = Vec::new();
+    for mount in mounts {
+        let key = group_by.key(mount);
+        match groups.iter_mut().find(|g| g.key == key) {
+            Some(group) => group.mounts.push(mount),
+            None => groups.push(Group {
+                key,
+                mounts: vec![mount],
+                totals: Totals::default(),
+            }),
+        }
+    }
+    for group in &mut groups {
+        group.totals = Totals::of(&group.mounts);
+    }
+    groups
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn test_group_totals() {
+    let stats: Value = serde_json::from_str(include_str!("../tests/fixtures/stats.json")).unwrap();
+    let mounts = crate::mount_source::mounts_from_fixture(
+        include_str!("../tests/fixtures/mountinfo"),
+        Some(&stats),
+    )
+    .unwrap();
+    let mounts: Vec<&Mount> = mounts.iter().filter(|m| m.stats().is_some()).collect();
+    let groups = group(&mounts, GroupBy::Type);
+    let keys: Vec<&str> = groups.iter().map(|g| g.key.as_str()).collect();
+    assert_eq!(keys, ["ext4", "tmpfs", "vfat"]);
+    // /srv/data and its bind mount /var/lib/app are counted once
+    let ext4 = &groups[0];
+    assert_eq!(ext4.mounts.len(), 3);
+    assert_eq!(ext4.totals.filesystems, 2);
+    assert_eq!(ext4.totals.size, (120000000 + 488281250) * 4096);
+    assert_eq!(ext4.totals.inodes, 30000000 + 122101760);
+    assert_eq!(Totals::of(&mounts).size, groups.iter().map(|g| g.totals.size).sum::<u64>());
+    let groups = group(&mounts, GroupBy::Device);
+    let keys: Vec<&str> = groups.iter().map(|g| g.key.as_str()).collect();
+    assert_eq!(keys, ["nvme0n1", "tmpfs", "sdb", "sdc"]);
+    assert_eq!(group(&mounts, GroupBy::Disk)[1].key, "other");
+}
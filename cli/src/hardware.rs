@@ -0,0 +1,101 @@
+use {
+    crate::sysfs::Sysfs,
+    lfs_core::{DeviceId, Mount},
+    std::{
+        collections::BTreeMap,
+        sync::{Arc, Mutex, OnceLock},
+    },
+};
+
+/// Details of the physical disk holding a filesystem, read from sysfs
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DiskHardware {
+    /// kernel name of the disk, eg `sda` or `nvme0n1`
+    pub disk: String,
+    pub vendor: Option<String>,
+    pub model: Option<String>,
+    pub serial: Option<String>,
+    pub transport: Option<&'static str>,
+    /// the active I/O scheduler, eg `mq-deadline` or `none`
+    pub scheduler: Option<String>,
+    pub logical_sector_size: Option<u64>,
+    pub physical_sector_size: Option<u64>,
+    /// whether the disk accepts discard (TRIM) requests
+    pub discard: Option<bool>,
+    /// read-ahead, in bytes
+    pub read_ahead: Option<u64>,
+}
+
+impl DiskHardware {
+    pub fn read(sysfs: &Sysfs, disk: &str) -> Self {
+        let attr = |name: &str| sysfs.attr(disk, name);
+        let attr_u64 = |name: &str| attr(name).and_then(|v| v.parse::<u64>().ok());
+        Self {
+            disk: disk.to_string(),
+            // SCSI vendors are names, but virtio and nvme ones are PCI ids
+            vendor: attr("device/vendor").filter(|v| !v.starts_with("0x")),
+            model: attr("device/model"),
+            serial: attr("device/serial").or_else(|| attr("serial")),
+            transport: sysfs.transport(disk),
+            scheduler: attr("queue/scheduler").map(|s| active_scheduler(&s)),
+            logical_sector_size: attr_u64("queue/logical_block_size"),
+            physical_sector_size: attr_u64("queue/physical_block_size"),
+            discard: attr_u64("queue/discard_max_bytes").map(|max| max > 0),
+            read_ahead: attr_u64("queue/read_ahead_kb").map(|kb| kb * 1024),
+        }
+    }
+    /// The hardware of the disk under the block device with id `dev`
+    pub fn of_dev(sysfs: &Sysfs, dev: DeviceId) -> Option<Self> {
+        let name = sysfs.name_of(dev)?;
+        Some(Self::read(sysfs, &sysfs.physical_disk(&name)))
+    }
+    /// Vendor and model, as displayed in the `model` column
+    pub fn vendor_model(&self) -> Option<String> {
+        match (&self.vendor, &self.model) {
+            (Some(vendor), Some(model)) if !model.starts_with(vendor.as_str()) => {
+                Some(format!("{} {}", vendor, model))
+            }
+            (_, Some(model)) => Some(model.clone()),
+            (Some(vendor), None) => Some(vendor.clone()),
+            (None, None) => None,
+        }
+    }
+    pub fn discard_str(&self) -> Option<&'static str> {
+        self.discard.map(|d| if d { "yes" } else { "no" })
+    }
+}
+
+/// The hardware of the disk of a mount, read once per device as the
+/// columns, filters and sorting all ask for it
+pub fn of_mount(mount: &Mount) -> Option<Arc<DiskHardware>> {
+    static CACHE: OnceLock<Mutex<BTreeMap<DeviceId, Option<Arc<DiskHardware>>>>> = OnceLock::new();
+    let dev = mount.info.dev;
+    let mut cache = CACHE.get_or_init(Default::default).lock().ok()?;
+    cache
+        .entry(dev)
+        .or_insert_with(|| DiskHardware::of_dev(&Sysfs::default(), dev).map(Arc::new))
+        .clone()
+}
+
+/// Extract the active scheduler from `queue/scheduler`, eg
+/// `none [mq-deadline] kyber bfq`
+fn active_scheduler(schedulers: &str) -> String {
+    schedulers
+        .split_whitespace()
+        .find_map(|s| s.strip_prefix('[').and_then(|s| s.strip_suffix(']')))
+        .unwrap_or(schedulers.trim())
+        .to_string()
+}
+
+#[test]
+fn test_active_scheduler() {
+    assert_eq!(active_scheduler("none [mq-deadline] kyber bfq \n"), "mq-deadline");
+    assert_eq!(active_scheduler("[none] mq-deadline"), "none");
+    assert_eq!(active_scheduler("none"), "none");
+    let hardware = DiskHardware {
+        vendor: Some("ATA".into()),
+        model: Some("Samsung SSD 870".into()),
+        ..Default::default()
+    };
+    assert_eq!(hardware.vendor_model().as_deref(), Some("ATA Samsung SSD 870"));
+}
@@ -0,0 +1,408 @@
+use {
+    crate::{
+        Args,
+        age::Age,
+        directory::display::make_skin,
+        process::graph::render_bar_compact,
+    },
+    lfs_core::{DeviceId, Mount, ReadOptions},
+    std::{
+        collections::HashMap,
+        fs::{self, File, OpenOptions},
+        io::{self, BufRead, BufReader, BufWriter, Write},
+        path::{Path, PathBuf},
+        cell::OnceCell,
+        time::{Duration, SystemTime, UNIX_EPOCH},
+    },
+    termimad::minimad::{OwningTemplateExpander, TableBuilder, Col, Alignment},
+};
+
+static SECONDS_PER_DAY: f64 = 86_400.0;
+
+/// Maximal number of rows of the history chart
+static CHART_ROWS: usize = 30;
+
+/// A measure of the usage of a mount
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Snapshot {
+    /// seconds since the epoch
+    pub time: u64,
+    pub used: u64,
+    pub size: u64,
+    pub inodes_used: Option<u64>,
+}
+
+/// The usage of the mounts over time, as recorded in the history file.
+///
+/// The file is append only, with one line per mount and snapshot:
+/// `time used size inodes_used|- key`, the key being last as it may
+/// hold spaces
+#[derive(Debug, Clone, Default)]
+pub struct History {
+    series: HashMap<String, Vec<Snapshot>>,
+}
+
+impl History {
+    /// `$XDG_DATA_HOME/provis/history`, or `~/.local/share/provis/history`
+    pub fn default_file() -> Option<PathBuf> {
+        let data_dir = std::env::var_os("XDG_DATA_HOME")
+            .filter(|dir| !dir.is_empty())
+            .map(PathBuf::from)
+            .or_else(|| std::env::var_os("HOME").map(|home| Path::new(&home).join(".local/share")))?;
+        Some(data_dir.join("provis").join("history"))
+    }
+    pub fn load(file: &Path) -> io::Result<Self> {
+        Ok(Self::read_from(BufReader::new(File::open(file)?)))
+    }
+    /// Read the snapshots, skipping the lines which can't be parsed,
+    /// eg one partially written
+    fn read_from<R: BufRead>(r: R) -> Self {
+        let mut history = Self::default();
+        for line in r.lines().map_while(Result::ok) {
+            let mut fields = line.splitn(5, ' ');
+            let mut next_u64 = || fields.next().and_then(|f| f.parse::<u64>().ok());
+            let (Some(time), Some(used), Some(size)) = (next_u64(), next_u64(), next_u64()) else {
+                continue;
+            };
+            let (Some(inodes_used), Some(key)) = (fields.next(), fields.next()) else {
+                continue;
+            };
+            let snapshot = Snapshot {
+                time,
+                used,
+                size,
+                inodes_used: inodes_used.parse().ok(),
+            };
+            history.series.entry(key.to_string()).or_default().push(snapshot);
+        }
+        for series in history.series.values_mut() {
+            series.sort_by_key(|s| s.time);
+        }
+        history
+    }
+    /// The snapshots of a mount, oldest first
+    pub fn series(&self, key: &str) -> &[Snapshot] {
+        self.series.get(key).map_or(&[], Vec::as_slice)
+    }
+}
+
+/// The key of a mount in the history: its filesystem UUID when
+/// there's one, as it survives a change of mount point
+pub fn mount_key(mount: &Mount) -> String {
+    match &mount.uuid {
+        Some(uuid) => format!("uuid:{}", uuid),
+        None => format!("mp:{}", mount.info.mount_point.to_string_lossy()),
+    }
+}
+
+fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs())
+}
+
+/// Append a snapshot of the mounts having stats to the history file,
+/// return the number of recorded mounts
+pub fn record(file: &Path, mounts: &[&Mount]) -> io::Result<usize> {
+    if let Some(dir) = file.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let mut w = BufWriter::new(OpenOptions::new().create(true).append(true).open(file)?);
+    let time = now_secs();
+    let mut count = 0;
+    for mount in mounts {
+        let Some(stats) = mount.stats() else {
+            continue;
+        };
+        let inodes_used = stats
+            .inodes
+            .as_ref()
+            .map_or("-".to_string(), |i| i.used().to_string());
+        writeln!(
+            w,
+            "{} {} {} {} {}",
+            time,
+            stats.used(),
+            stats.size(),
+            inodes_used,
+            mount_key(mount),
+        )?;
+        count += 1;
+    }
+    w.flush()?;
+    Ok(count)
+}
+
+/// How the usage of a mount evolves
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Trend {
+    /// bytes per day, negative when space is freed
+    pub growth_per_day: f64,
+    /// time until the mount is full, when it grows
+    pub eta_full: Option<Duration>,
+}
+
+/// Compute the trend of the snapshots taken in the `window` before `now`
+/// by linear regression, `available` being the space left now
+pub fn trend(
+    series: &[Snapshot],
+    window: Duration,
+    now: u64,
+    available: u64,
+) -> Option<Trend> {
+    let start = now.saturating_sub(window.as_secs());
+    let points: Vec<(f64, f64)> = series
+        .iter()
+        .filter(|s| s.time >= start)
+        .map(|s| ((s.time - start) as f64, s.used as f64))
+        .collect();
+    if points.len() < 2 {
+        return None;
+    }
+    let n = points.len() as f64;
+    let mean_t = points.iter().map(|p| p.0).sum::<f64>() / n;
+    let mean_u = points.iter().map(|p| p.1).sum::<f64>() / n;
+    let var_t: f64 = points.iter().map(|p| (p.0 - mean_t).powi(2)).sum();
+    if var_t == 0.0 {
+        return None;
+    }
+    let cov: f64 = points.iter().map(|p| (p.0 - mean_t) * (p.1 - mean_u)).sum();
+    let slope = cov / var_t; // bytes per second
+    // a nearly flat growth may never fill the mount in any representable time
+    let eta_full = if slope > 0.0 {
+        Duration::try_from_secs_f64(available as f64 / slope).ok()
+    } else {
+        None
+    };
+    Some(Trend {
+        growth_per_day: slope * SECONDS_PER_DAY,
+        eta_full,
+    })
+}
+
+/// The trends of the mounts, computed over a window ending now
+/// from a history which is only read when a trend is needed
+#[derive(Debug)]
+pub struct Trends {
+    window: Duration,
+    now: u64,
+    history: OnceCell<History>,
+}
+
+impl Trends {
+    /// Trends from the default history file
+    pub fn new(window: Duration) -> Self {
+        Self {
+            window,
+            now: now_secs(),
+            history: OnceCell::new(),
+        }
+    }
+    /// Trends from an already loaded history
+    pub fn from_history(
+        history: History,
+        window: Duration,
+        now: u64,
+    ) -> Self {
+        Self {
+            window,
+            now,
+            history: OnceCell::from(history),
+        }
+    }
+    fn history(&self) -> &History {
+        self.history.get_or_init(|| {
+            History::default_file()
+                .and_then(|file| History::load(&file).ok())
+                .unwrap_or_default()
+        })
+    }
+    /// The trend of a mount
+    pub fn of_mount(
+        &self,
+        mount: &Mount,
+    ) -> Option<Trend> {
+        let available = mount.stats()?.available();
+        trend(self.history().series(&mount_key(mount)), self.window, self.now, available)
+    }
+}
+
+/// Format a time as `YYYY-MM-DD HH:MM`, in UTC
+fn format_time(secs: u64) -> String {
+    // civil from days, after Howard Hinnant
+    let days = (secs / 86_400) as i64;
+    let rem = secs % 86_400;
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    format!(
+        "{:04}-{:02}-{:02} {:02}:{:02}",
+        year,
+        month,
+        day,
+        rem / 3600,
+        (rem % 3600) / 60,
+    )
+}
+
+/// Chart the recorded usage of the mount holding `path`
+pub fn display_history<W: Write>(
+    w: &mut W,
+    path: &Path,
+    args: &Args,
+) -> io::Result<()> {
+    let Some(file) = History::default_file() else {
+        eprintln!("No data directory found for the history");
+        return Ok(());
+    };
+    let history = match History::load(&file) {
+        Ok(history) => history,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => {
+            eprintln!("No history yet, record some with `provis --record` (eg in a cron job)");
+            return Ok(());
+        }
+        Err(e) => {
+            eprintln!("Error reading {}: {}", file.display(), e);
+            return Ok(());
+        }
+    };
+    let mut options = ReadOptions::default();
+    if let Some(timeout) = args.timeout {
+        options = options.stats_timeout(timeout.as_duration());
+    }
+    let mounts = match lfs_core::read_mounts(&options) {
+        Ok(mounts) => mounts,
+        Err(e) => {
+            eprintln!("Error reading mounts: {}", e);
+            return Ok(());
+        }
+    };
+    let mount = match mounts.iter().find(|m| m.info.mount_point == path) {
+        Some(mount) => mount,
+        None => {
+            let dev = match DeviceId::of_path(path) {
+                Ok(dev) => dev,
+                Err(e) => {
+                    eprintln!("Error getting device of path {}: {}", path.display(), e);
+                    return Ok(());
+                }
+            };
+            match mounts.iter().find(|m| m.info.dev == dev) {
+                Some(mount) => mount,
+                None => {
+                    eprintln!("No mount found for {}", path.display());
+                    return Ok(());
+                }
+            }
+        }
+    };
+    let key = mount_key(mount);
+    let now = now_secs();
+    let window = args.history_window.as_duration();
+    let start = now.saturating_sub(window.as_secs());
+    let series: Vec<Snapshot> = history
+        .series(&key)
+        .iter()
+        .filter(|s| s.time >= start)
+        .copied()
+        .collect();
+    if series.is_empty() {
+        return writeln!(
+            w,
+            "No snapshot of {} ({}) in the last {}",
+            mount.info.mount_point.display(),
+            key,
+            Age(window),
+        );
+    }
+
+    // evenly spaced snapshots, the last one always shown
+    let step = series.len().div_euclid(CHART_ROWS) + usize::from(series.len() % CHART_ROWS != 0);
+    let mut shown: Vec<&Snapshot> = series.iter().rev().step_by(step.max(1)).collect();
+    shown.reverse();
+    let mut expander = OwningTemplateExpander::new();
+    expander.set_default("");
+    let mut previous: Option<&Snapshot> = None;
+    for snapshot in shown {
+        let pct = 100.0 * snapshot.used as f32 / snapshot.size.max(1) as f32;
+        let sub = expander
+            .sub("rows")
+            .set("time", format_time(snapshot.time))
+            .set("used", args.units.fmt(snapshot.used))
+            .set("use", format!("{:.0}%", pct))
+            .set_md("bar", render_bar_compact(pct, 20, args.ascii));
+        if let Some(previous) = previous {
+            let change = snapshot.used as i64 - previous.used as i64;
+            sub.set("change", args.units.fmt_signed(change));
+        }
+        previous = Some(snapshot);
+    }
+    let mut tbl = TableBuilder::default();
+    tbl.col(Col::new("Time (UTC)", "${time}").align_content(Alignment::Left))
+        .col(Col::new("Used", "${used}").align_content(Alignment::Right))
+        .col(Col::new("Change", "${change}").align_content(Alignment::Right))
+        .col(Col::new("Use", "${use}").align_content(Alignment::Right))
+        .col(Col::new("Usage", "${bar}").align_content(Alignment::Left));
+    writeln!(
+        w,
+        "\nUsage of {} ({}), {} snapshots in the last {}:\n",
+        mount.info.mount_point.display(),
+        key,
+        series.len(),
+        Age(window),
+    )?;
+    make_skin(args).write_owning_expander_md(w, &expander, &tbl)?;
+    let available = mount.stats().map_or(0, |s| s.available());
+    match trend(&series, window, now, available) {
+        Some(trend) => {
+            write!(w, "Growth: {}/day", args.units.fmt_signed(trend.growth_per_day as i64))?;
+            match trend.eta_full {
+                Some(eta) => writeln!(w, ", full in {}", Age(eta).precise()),
+                None => writeln!(w, ", not filling up"),
+            }
+        }
+        None => writeln!(w, "Not enough snapshots for a trend"),
+    }
+}
+
+#[test]
+fn test_history_trend() {
+    let content = "\
+        1000 100 1000 5 uuid:abc\n\
+        1000 7 50 - mp:/mnt/with space\n\
+        broken\n\
+        87400 200 1000 6 uuid:abc\n\
+        173800 300 1000 7 uuid:abc\n\
+    ";
+    let history = History::read_from(content.as_bytes());
+    let series = history.series("uuid:abc");
+    assert_eq!(series.len(), 3);
+    assert_eq!(series[2].inodes_used, Some(7));
+    assert_eq!(history.series("mp:/mnt/with space")[0].inodes_used, None);
+    // 100 bytes per day, 700 bytes left
+    let window = Duration::from_secs(10 * 86_400);
+    let filling = trend(series, window, 173_800, 700).unwrap();
+    assert!((filling.growth_per_day - 100.0).abs() < 1e-6);
+    assert_eq!(filling.eta_full.map(|d| d.as_secs()), Some(7 * 86_400));
+    // a slow growth on a huge filesystem doesn't give a representable eta
+    let huge = trend(series, window, 173_800, u64::MAX).unwrap();
+    assert_eq!(huge.eta_full, None);
+    assert_eq!(format_time(0), "1970-01-01 00:00");
+    assert_eq!(format_time(1_700_000_000), "2023-11-14 22:13");
+}
+
+#[test]
+fn test_history_command() {
+    use {clap::Parser, crate::args::Command};
+    let args = Args::try_parse_from(["provis", "history", "/var"]).unwrap();
+    assert!(matches!(args.command, Some(Command::History { mount }) if mount == Path::new("/var")));
+    let args = Args::try_parse_from(["provis", "/var"]).unwrap();
+    assert!(args.command.is_none());
+    assert_eq!(args.path.as_deref(), Some(Path::new("/var")));
+}
@@ -1,5 +1,11 @@
 use {
-    crate::units::Units,
+    crate::{
+        group::{Group, Totals},
+        hardware,
+        history::Trends,
+        reserved,
+        units::Units,
+    },
     lfs_core::*,
     serde_json::{
         Value,
@@ -10,6 +16,7 @@ use {
 pub fn output_value(
     mounts: &[&Mount],
     units: Units,
+    trends: &Trends,
 ) -> Value {
     Value::Array(
         mounts
@@ -36,6 +43,9 @@ pub fn output_value(
                             "used": units.fmt(s.used()),
                             "used-percent": format!("{:.0}%", 100.0*s.use_share()),
                             "available": units.fmt(s.available()),
+                            "reserved": units.fmt(reserved::reserved(s)),
+                            "reserved-percent": format!("{:.1}%", 100.0*reserved::reserved_share(s)),
+                            "free-for-root": units.fmt(reserved::free_for_root(s)),
                             "inodes": inodes,
                         })
                     }
@@ -52,12 +62,38 @@ pub fn output_value(
                     }
                 });
                 let disk = mount.disk.as_ref().map(|d| {
-                    json!({
+                    #[allow(unused_mut)]
+                    let mut disk = json!({
                         "type": d.disk_type(),
                         "rotational": d.rotational,
                         "removable": d.removable,
                         "crypted": d.crypted,
                         "ram": d.ram,
+                    });
+                    #[cfg(not(windows))]
+                    {
+                        disk["name"] = json!(d.name);
+                    }
+                    disk
+                });
+                let hardware = hardware::of_mount(mount).map(|h| {
+                    json!({
+                        "disk": h.disk,
+                        "vendor": h.vendor,
+                        "model": h.model,
+                        "serial": h.serial,
+                        "transport": h.transport,
+                        "scheduler": h.scheduler,
+                        "logical-sector-size": h.logical_sector_size,
+                        "physical-sector-size": h.physical_sector_size,
+                        "discard": h.discard,
+                        "read-ahead": h.read_ahead,
+                    })
+                });
+                let trend = trends.of_mount(mount).map(|t| {
+                    json!({
+                        "growth-per-day": t.growth_per_day.round() as i64,
+                        "eta-full-secs": t.eta_full.map(|eta| eta.as_secs()),
                     })
                 });
                 let dev = {
@@ -78,10 +114,14 @@ pub fn output_value(
                     "dev": dev,
                     "fs": mount.info.fs,
                     "fs-label": mount.fs_label,
+                    "uuid": mount.uuid,
+                    "part-uuid": mount.part_uuid,
                     "fs-type": mount.info.fs_type,
                     "mount-point": mount.info.mount_point,
                     "options": mount.info.options_string(),
                     "disk": disk,
+                    "hardware": hardware,
+                    "trend": trend,
                     "stats": stats,
                     "bound": mount.info.bound,
                     "remote": mount.is_remote(),
@@ -91,3 +131,26 @@ pub fn output_value(
             .collect(),
     )
 }
+
+/// The groups of `--group-by`, with their mounts and subtotals, then the total
+pub fn groups_value(
+    groups: &[Group],
+    units: Units,
+    trends: &Trends,
+) -> Value {
+    let mounts: Vec<&Mount> = groups.iter().flat_map(|g| g.mounts.iter().copied()).collect();
+    let groups: Vec<Value> = groups
+        .iter()
+        .map(|group| {
+            json!({
+                "group": group.key,
+                "mounts": output_value(&group.mounts, units, trends),
+                "totals": group.totals.to_json(units),
+            })
+        })
+        .collect();
+    json!({
+        "groups": groups,
+        "totals": Totals::of(&mounts).to_json(units),
+    })
+}
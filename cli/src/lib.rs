@@ -1,25 +1,40 @@
+pub mod age;
 pub mod args;
 pub mod col;
 pub mod col_expr;
 pub mod cols;
 pub mod csv;
+pub mod diff;
+pub mod diskio;
 pub mod filter;
+pub mod group;
+pub mod hardware;
 pub mod help;
+pub mod history;
 pub mod json;
+pub mod limit;
 pub mod list_cols;
+pub mod mount_source;
+#[cfg(target_os = "linux")]
+pub mod namespace;
 pub mod normal;
 pub mod order;
+pub mod reserved;
 pub mod sorting;
+pub mod sysfs;
 pub mod table;
 pub mod timeout;
 pub mod units;
 pub mod process;
 pub mod directory;
+pub mod devices;
+pub mod volumes;
 pub mod commands;
 
 use {
     crate::{
         args::*,
+        mount_source::*,
         normal::*,
     },
     clap::Parser,
@@ -62,44 +77,84 @@ pub fn run() -> io::Result<()> {
         return Ok(());
     }
 
+    match &args.command {
+        Some(Command::History { mount }) => {
+            return history::display_history(&mut w, mount, &args);
+        }
+        Some(Command::Diff { before, after }) => {
+            return diff::display_diff(&mut w, before, after, &args);
+        }
+        None => {}
+    }
+
     // Route to directory size view
     if args.size_on_disk {
         return directory::display_directories(&mut w, &args);
     }
 
+    // Route to block device tree
+    if args.devices {
+        return devices::display_devices(&mut w, &args);
+    }
+
+    // Route to btrfs subvolumes and zfs datasets
+    if args.volumes {
+        return volumes::display_volumes(&mut w, &args);
+    }
+
     // Route to process view
     if args.processes {
         return process::display_processes(&mut w, &args);
     }
 
     // Default: disk view
-    let mut options =
-        lfs_core::ReadOptions::default()
-        .remote_stats(args.remote_stats.unwrap_or_else(|| true));
-    if let Some(timeout) = args.timeout {
-        options = options.stats_timeout(timeout.as_duration());
-    }
-    if let Some(strategy) = &args.strategy {
-        match strategy.parse() {
-            Ok(strategy) => {
-                options = options.strategy(strategy);
-            }
-            Err(_) => {
-                eprintln!("Ignoring unrecognized strategy");
-            }
-        }
-    }
-    let mut mounts = match lfs_core::read_mounts(&options) {
+    let mut mounts = match mount_source(&args).and_then(|source| source.read_mounts()) {
         Ok(mounts) => mounts,
         Err(e) => {
-            eprintln!("Error reading mounts: {}", e);
+            eprintln!("{}", e);
+            return Ok(());
+        }
+    };
+    // the mounts as seen by another process, and the ones of the host
+    let host_mounts = match namespace_mounts(&args, &mut mounts) {
+        Ok(host_mounts) => host_mounts,
+        Err(e) => {
+            eprintln!("{}", e);
             return Ok(());
         }
     };
     if !args.all {
+        #[cfg(target_os = "linux")]
+        if host_mounts.is_some() {
+            mounts.retain(namespace::is_normal_in_namespace);
+        } else {
+            mounts.retain(is_normal);
+        }
+        #[cfg(not(target_os = "linux"))]
         mounts.retain(is_normal);
     }
-    if let Some(path) = &args.path {
+    if args.record {
+        let Some(file) = history::History::default_file() else {
+            eprintln!("No data directory found for the history");
+            return Ok(());
+        };
+        let mounts: Vec<&lfs_core::Mount> = mounts.iter().collect();
+        match history::record(&file, &mounts) {
+            Ok(count) => eprintln!("Recorded {} mounts in {}", count, file.display()),
+            Err(e) => eprintln!("Error writing {}: {}", file.display(), e),
+        }
+        return Ok(());
+    }
+    if let (Some(path), true) = (&args.path, host_mounts.is_some() || args.mountinfo_file.is_some()) {
+        // the path isn't on this system, so it's matched against the mount points
+        let mount_point = mounts
+            .iter()
+            .map(|m| &m.info.mount_point)
+            .filter(|mp| path.starts_with(mp))
+            .max_by_key(|mp| mp.components().count())
+            .cloned();
+        mounts.retain(|m| Some(&m.info.mount_point) == mount_point.as_ref());
+    } else if let Some(path) = &args.path {
         let dev = match lfs_core::DeviceId::of_path(path) {
             Ok(dev) => dev,
             Err(e) => {
@@ -109,8 +164,9 @@ pub fn run() -> io::Result<()> {
         };
         mounts.retain(|m| m.info.dev == dev);
     }
-    args.sort.sort(&mut mounts);
-    let mounts = match args.filter.clone().unwrap_or_default().filter(&mounts) {
+    let trends = history::Trends::new(args.history_window.as_duration());
+    args.sort.sort(&mut mounts, &trends);
+    let mounts = match args.filter.clone().unwrap_or_default().filter(&mounts, &trends) {
         Ok(mounts) => mounts,
         Err(e) => {
             eprintln!("Error in filter evaluation: {}", e);
@@ -118,25 +174,94 @@ pub fn run() -> io::Result<()> {
         }
     };
     if args.csv {
-        return csv::write(&mut w, &mounts, &args);
+        return csv::write(&mut w, &mounts, &args, &trends);
     }
+    let groups = args.group_by.map(|group_by| group::group(&mounts, group_by));
     if args.json {
-        return writeln!(
-            &mut w,
-            "{}",
-            serde_json::to_string_pretty(&json::output_value(&mounts, args.units)).unwrap()
-        );
+        let value = match &groups {
+            Some(groups) => json::groups_value(groups, args.units, &trends),
+            None => json::output_value(&mounts, args.units, &trends),
+        };
+        return writeln!(&mut w, "{}", serde_json::to_string_pretty(&value).unwrap());
     }
     if mounts.is_empty() {
         return writeln!(&mut w, "no mount to display - try\n    provis -a");
     }
-    table::write(&mut w, &mounts, args.color(), &args)?;
+    match &groups {
+        Some(groups) => table::write_groups(&mut w, groups, args.color(), &args, &trends)?,
+        None => table::write(&mut w, &mounts, args.color(), &args, &trends)?,
+    }
+    #[cfg(target_os = "linux")]
+    if let Some(host_mounts) = &host_mounts {
+        namespace::write_host_sources(&mut w, &mounts, host_mounts, &args)?;
+    }
     if args.color() {
         csi_reset();
     }
     Ok(())
 }
 
+/// The captured mounts given with `--mountinfo-file`, or else the ones
+/// of the running system
+fn mount_source(args: &Args) -> Result<Box<dyn MountSource>, String> {
+    if let Some(mountinfo_file) = &args.mountinfo_file {
+        #[cfg(target_os = "linux")]
+        return Ok(Box::new(FixtureSource::new(
+            mountinfo_file.clone(),
+            args.stats_file.clone(),
+        )));
+        #[cfg(not(target_os = "linux"))]
+        return Err(format!(
+            "Can't read {}: --mountinfo-file is only available on linux",
+            mountinfo_file.display(),
+        ));
+    }
+    let mut options =
+        lfs_core::ReadOptions::default()
+        .remote_stats(args.remote_stats.unwrap_or_else(|| true));
+    if let Some(timeout) = args.timeout {
+        options = options.stats_timeout(timeout.as_duration());
+    }
+    if let Some(strategy) = &args.strategy {
+        match strategy.parse() {
+            Ok(strategy) => {
+                options = options.strategy(strategy);
+            }
+            Err(_) => {
+                eprintln!("Ignoring unrecognized strategy");
+            }
+        }
+    }
+    Ok(Box::new(LiveSource::new(options)))
+}
+
+/// With `--pid` or `--container`, replace the mounts with the ones of the
+/// target process and return the ones of the host
+#[cfg(target_os = "linux")]
+fn namespace_mounts(
+    args: &Args,
+    mounts: &mut Vec<lfs_core::Mount>,
+) -> Result<Option<Vec<lfs_core::Mount>>, String> {
+    let pid = match (&args.container, args.pid) {
+        (Some(id), _) => namespace::container_pid(id)?,
+        (None, Some(pid)) => pid,
+        (None, None) => return Ok(None),
+    };
+    let pid_mounts = namespace::read_mounts_of_pid(pid, mounts)?;
+    Ok(Some(std::mem::replace(mounts, pid_mounts)))
+}
+
+#[cfg(not(target_os = "linux"))]
+fn namespace_mounts(
+    args: &Args,
+    _mounts: &mut Vec<lfs_core::Mount>,
+) -> Result<Option<Vec<lfs_core::Mount>>, String> {
+    if args.pid.is_some() || args.container.is_some() {
+        return Err("--pid and --container are only available on linux".to_string());
+    }
+    Ok(None)
+}
+
 /// output a Reset CSI sequence
 fn csi_reset() {
     print!("\u{1b}[0m");
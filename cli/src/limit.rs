@@ -0,0 +1,51 @@
+use {
+    std::str::FromStr,
+};
+
+/// A maximal number of results, `None` meaning all of them
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Limit(Option<usize>);
+
+impl Limit {
+    pub fn count(self) -> Option<usize> {
+        self.0
+    }
+    /// Number of items to keep among `len`
+    pub fn apply(self, len: usize) -> usize {
+        self.0.map_or(len, |n| n.min(len))
+    }
+    /// The limit as a count, `usize::MAX` standing for all
+    pub fn or_max(self) -> usize {
+        self.0.unwrap_or(usize::MAX)
+    }
+    fn try_read(s: &str) -> Option<Self> {
+        if s == "all" {
+            return Some(Self(None));
+        }
+        match s.parse::<usize>() {
+            Ok(n) if n > 0 => Some(Self(Some(n))),
+            _ => None,
+        }
+    }
+}
+
+impl FromStr for Limit {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::try_read(s)
+            .ok_or(r#"Invalid limit, expected "all" or a positive number"#)
+    }
+}
+
+#[test]
+fn test_limit_parsing() {
+    assert_eq!("all".parse(), Ok(Limit(None)));
+    assert_eq!("35".parse(), Ok(Limit(Some(35))));
+    assert!("0".parse::<Limit>().is_err());
+    assert!("-3".parse::<Limit>().is_err());
+    assert!("twenty".parse::<Limit>().is_err());
+    assert_eq!(Limit(Some(35)).apply(12), 12);
+    assert_eq!(Limit(None).apply(12), 12);
+    assert_eq!(Limit(Some(5)).apply(12), 5);
+}
@@ -0,0 +1,198 @@
+use {
+    lfs_core::{Mount, ReadOptions},
+};
+#[cfg(target_os = "linux")]
+use {
+    lfs_core::{Disk, Inodes, MountInfo, Stats, StatsError},
+    serde_json::Value,
+    std::{
+        collections::HashMap,
+        fs,
+        path::PathBuf,
+    },
+};
+
+/// Where the disk view gets its mounts from
+pub trait MountSource {
+    fn read_mounts(&self) -> Result<Vec<Mount>, String>;
+}
+
+/// The mounts of the running system
+pub struct LiveSource {
+    options: ReadOptions,
+}
+
+impl LiveSource {
+    pub fn new(options: ReadOptions) -> Self {
+        Self { options }
+    }
+}
+
+impl MountSource for LiveSource {
+    fn read_mounts(&self) -> Result<Vec<Mount>, String> {
+        lfs_core::read_mounts(&self.options).map_err(|e| format!("Error reading mounts: {}", e))
+    }
+}
+
+/// Mounts read from a captured system state: a `mountinfo` file (eg a copy of
+/// `/proc/self/mountinfo`) and optionally the JSON written by `provis -a -j`
+/// on the same system, which gives the stats, disks, labels and UUIDs.
+///
+/// Without the JSON, the mounts have no stats, so `--all` is needed to see them
+#[cfg(target_os = "linux")]
+pub struct FixtureSource {
+    mountinfo_file: PathBuf,
+    stats_file: Option<PathBuf>,
+}
+
+#[cfg(target_os = "linux")]
+impl FixtureSource {
+    pub fn new(
+        mountinfo_file: PathBuf,
+        stats_file: Option<PathBuf>,
+    ) -> Self {
+        Self {
+            mountinfo_file,
+            stats_file,
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+impl MountSource for FixtureSource {
+    fn read_mounts(&self) -> Result<Vec<Mount>, String> {
+        let read = |path: &PathBuf| {
+            fs::read_to_string(path).map_err(|e| format!("Error reading {}: {}", path.display(), e))
+        };
+        let mountinfo = read(&self.mountinfo_file)?;
+        let stats = match &self.stats_file {
+            Some(path) => {
+                let value = serde_json::from_str(&read(path)?)
+                    .map_err(|e| format!("Invalid JSON in {}: {}", path.display(), e))?;
+                Some(value)
+            }
+            None => None,
+        };
+        mounts_from_fixture(&mountinfo, stats.as_ref())
+    }
+}
+
+/// Build the mounts from the content of a mountinfo file and the
+/// JSON of the disk view
+#[cfg(target_os = "linux")]
+pub fn mounts_from_fixture(
+    mountinfo: &str,
+    stats: Option<&Value>,
+) -> Result<Vec<Mount>, String> {
+    let mut details: HashMap<&str, &Value> = HashMap::new();
+    if let Some(stats) = stats {
+        let items = stats.as_array().ok_or("The stats file must be the JSON of `provis -a -j`")?;
+        for item in items {
+            if let Some(mp) = item.get("mount-point").and_then(Value::as_str) {
+                details.insert(mp, item);
+            }
+        }
+    }
+    let mut infos: Vec<MountInfo> = Vec::new();
+    for line in mountinfo.lines().filter(|line| !line.trim().is_empty()) {
+        let mut info: MountInfo = line
+            .parse()
+            .map_err(|_| format!("Invalid mountinfo line: {:?}", line))?;
+        // same rule than lfs-core for the live system
+        info.bound = infos.iter().any(|i| i.dev == info.dev);
+        infos.push(info);
+    }
+    Ok(infos
+        .into_iter()
+        .map(|info| {
+            let item = details.get(info.mount_point.to_string_lossy().as_ref()).copied();
+            let str_at = |key: &str| item?.get(key)?.as_str().map(String::from);
+            Mount {
+                fs_label: str_at("fs-label"),
+                uuid: str_at("uuid"),
+                part_uuid: str_at("part-uuid"),
+                disk: item.and_then(|item| disk_from_json(item.get("disk")?)),
+                stats: match item {
+                    Some(item) => stats_from_json(item),
+                    None => Err(StatsError::Excluded),
+                },
+                info,
+            }
+        })
+        .collect())
+}
+
+#[cfg(target_os = "linux")]
+fn stats_from_json(item: &Value) -> Result<Stats, StatsError> {
+    let Some(stats) = item.get("stats").filter(|s| s.is_object()) else {
+        let unreachable = item.get("unreachable").and_then(Value::as_bool) == Some(true);
+        return Err(if unreachable { StatsError::Unreachable } else { StatsError::Excluded });
+    };
+    let u64_at = |v: &Value, key: &str| v.get(key).and_then(Value::as_u64);
+    let inodes = stats.get("inodes").and_then(|i| {
+        Inodes::new(u64_at(i, "files")?, u64_at(i, "free")?, u64_at(i, "avail")?)
+    });
+    (|| {
+        Some(Stats {
+            bsize: u64_at(stats, "bsize")?,
+            blocks: u64_at(stats, "blocks")?,
+            bused: u64_at(stats, "bused")?,
+            bfree: u64_at(stats, "bfree")?,
+            bavail: u64_at(stats, "bavail")?,
+            inodes,
+        })
+    })()
+    .ok_or(StatsError::Unconsistent)
+}
+
+#[cfg(target_os = "linux")]
+fn disk_from_json(disk: &Value) -> Option<Disk> {
+    let bool_at = |key: &str| disk.get(key).and_then(Value::as_bool);
+    let disk_type = disk.get("type")?.as_str()?;
+    Some(Disk {
+        name: disk.get("name").and_then(Value::as_str).unwrap_or_default().to_string(),
+        rotational: bool_at("rotational"),
+        removable: bool_at("removable"),
+        read_only: None,
+        ram: bool_at("ram").unwrap_or(false),
+        image: disk_type == "imag",
+        lvm: disk_type == "LVM",
+        crypted: bool_at("crypted").unwrap_or(false),
+    })
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn test_fixture_disk_view() {
+    use {
+        crate::{Args, filter::Filter, history::Trends, json, normal::is_normal, sorting::Sorting, table},
+        clap::Parser,
+    };
+    let stats: Value = serde_json::from_str(include_str!("../tests/fixtures/stats.json")).unwrap();
+    let mut mounts = mounts_from_fixture(include_str!("../tests/fixtures/mountinfo"), Some(&stats)).unwrap();
+    let trends = Trends::from_history(Default::default(), std::time::Duration::from_secs(30 * 86_400), 0);
+    assert_eq!(mounts.len(), 7);
+    mounts.retain(is_normal);
+    let mount_points = |mounts: &[&Mount]| -> Vec<String> {
+        mounts.iter().map(|m| m.info.mount_point.to_string_lossy().to_string()).collect()
+    };
+    "size-desc".parse::<Sorting>().unwrap().sort(&mut mounts, &trends);
+    let all: Vec<&Mount> = mounts.iter().collect();
+    assert_eq!(mount_points(&all), ["/srv/data", "/", "/mnt/usb"]);
+    let filter: Filter = "reserved>4% & type=ext4".parse().unwrap();
+    let filtered = filter.filter(&mounts, &trends).unwrap();
+    assert_eq!(mount_points(&filtered), ["/srv/data"]);
+
+    let value = json::output_value(&filtered, Default::default(), &trends);
+    assert_eq!(value[0]["fs-label"], "data");
+    assert_eq!(value[0]["stats"]["reserved"], "100G");
+    assert_eq!(value[0]["disk"]["type"], "HDD");
+
+    let args = Args::try_parse_from(["provis", "--color", "no", "-c", "fs+type+disk+free+rsv+mp"]).unwrap();
+    let mut w = Vec::new();
+    table::write(&mut w, &all, false, &args, &trends).unwrap();
+    let table = String::from_utf8(w).unwrap();
+    let row = table.lines().find(|line| line.contains("/srv/data")).unwrap();
+    assert!(row.contains("/dev/sdb1") && row.contains("HDD") && row.contains("100G"));
+    assert!(table.lines().any(|line| line.contains("/mnt/usb") && line.contains("remov")));
+}
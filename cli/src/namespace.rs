@@ -0,0 +1,278 @@
+use {
+    crate::{
+        Args,
+        directory::display::make_skin,
+        normal::is_normal,
+    },
+    lfs_core::{DeviceId, Mount, MountInfo, StatsError},
+    std::{
+        fs,
+        io::{self, Write},
+        path::{Path, PathBuf},
+        process::Command,
+    },
+    termimad::minimad::{OwningTemplateExpander, TableBuilder, Col, Alignment},
+};
+
+/// The mount table of another process, which may live in another
+/// mount namespace, eg a container
+pub fn read_mounts_of_pid(
+    pid: u32,
+    host_mounts: &[Mount],
+) -> Result<Vec<Mount>, String> {
+    let path = format!("/proc/{}/mountinfo", pid);
+    let content = fs::read_to_string(&path).map_err(|e| format!("Error reading {}: {}", path, e))?;
+    Ok(parse_mountinfo(&content)
+        .into_iter()
+        .map(|info| with_host_details(info, host_mounts))
+        .collect())
+}
+
+/// Parse a mountinfo file, ignoring the lines which can't be parsed.
+///
+/// Contrary to the host view, a mount is only considered bound when it
+/// repeats the same directory of the same filesystem, as the bind mounts
+/// of a container are its volumes
+pub fn parse_mountinfo(content: &str) -> Vec<MountInfo> {
+    let mut infos: Vec<MountInfo> = Vec::new();
+    for line in content.lines() {
+        let Ok(mut info) = line.parse::<MountInfo>() else {
+            continue;
+        };
+        info.bound = infos.iter().any(|i| i.dev == info.dev && i.root == info.root);
+        infos.push(info);
+    }
+    infos
+}
+
+/// Statistics are per filesystem, so they're the ones of the host
+/// mount of the same device, as are the disk, label and UUIDs.
+///
+/// An overlay only mounted in the namespace gets the ones of the
+/// filesystem of its upper layer, where its writes go
+fn with_host_details(
+    info: MountInfo,
+    host_mounts: &[Mount],
+) -> Mount {
+    let host = host_mount_of(info.dev, host_mounts).or_else(|| {
+        let upper = Path::new(info.option_value("upperdir")?);
+        host_mounts
+            .iter()
+            .filter(|m| upper.starts_with(&m.info.mount_point))
+            .max_by_key(|m| m.info.mount_point.components().count())
+    });
+    match host {
+        Some(host) => Mount {
+            info,
+            fs_label: host.fs_label.clone(),
+            disk: host.disk.clone(),
+            stats: host.stats.clone(),
+            uuid: host.uuid.clone(),
+            part_uuid: host.part_uuid.clone(),
+        },
+        None => Mount {
+            info,
+            fs_label: None,
+            disk: None,
+            stats: Err(StatsError::Unreachable),
+            uuid: None,
+            part_uuid: None,
+        },
+    }
+}
+
+/// The host mount of a device showing the biggest part of the filesystem
+fn host_mount_of(
+    dev: DeviceId,
+    host_mounts: &[Mount],
+) -> Option<&Mount> {
+    host_mounts
+        .iter()
+        .filter(|m| m.info.dev == dev)
+        .min_by_key(|m| m.info.root.components().count())
+}
+
+/// Whether a mount of another namespace should be shown without `--all`:
+/// the overlay root of a container has no disk but is the first place
+/// its processes write to
+pub fn is_normal_in_namespace(m: &Mount) -> bool {
+    is_normal(m) || (m.info.fs_type == "overlay" && m.stats().is_some())
+}
+
+/// Where the content of a mount of another namespace lives on the host
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HostSource {
+    /// the writable layer of an overlay
+    Upper(PathBuf),
+    /// a read-only layer of an overlay, the topmost first
+    Lower(PathBuf),
+    /// a directory of a filesystem also mounted on the host
+    Bind(PathBuf),
+    /// a whole filesystem also mounted on the host
+    Mount(PathBuf),
+}
+
+impl HostSource {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Self::Upper(_) => "upper",
+            Self::Lower(_) => "lower",
+            Self::Bind(_) => "bind",
+            Self::Mount(_) => "mount",
+        }
+    }
+    pub fn path(&self) -> &Path {
+        match self {
+            Self::Upper(p) | Self::Lower(p) | Self::Bind(p) | Self::Mount(p) => p,
+        }
+    }
+}
+
+/// The host paths holding the content of a mount of another namespace
+pub fn host_sources(
+    info: &MountInfo,
+    host_mounts: &[Mount],
+) -> Vec<HostSource> {
+    if info.fs_type == "overlay" {
+        let mut sources = Vec::new();
+        if let Some(upper) = info.option_value("upperdir") {
+            sources.push(HostSource::Upper(upper.into()));
+        }
+        if let Some(lower) = info.option_value("lowerdir") {
+            sources.extend(
+                lower
+                    .split(':')
+                    .filter(|l| !l.is_empty())
+                    .map(|l| HostSource::Lower(l.into())),
+            );
+        }
+        return sources;
+    }
+    let Some(host) = host_mount_of(info.dev, host_mounts) else {
+        return Vec::new();
+    };
+    // both roots are paths in the filesystem
+    let Ok(relative) = info.root.strip_prefix(&host.info.root) else {
+        return Vec::new();
+    };
+    if relative.as_os_str().is_empty() {
+        vec![HostSource::Mount(host.info.mount_point.clone())]
+    } else {
+        vec![HostSource::Bind(host.info.mount_point.join(relative))]
+    }
+}
+
+/// Write, for the mounts of another namespace, where their content is on the host
+pub fn write_host_sources<W: Write>(
+    w: &mut W,
+    mounts: &[&Mount],
+    host_mounts: &[Mount],
+    args: &Args,
+) -> io::Result<()> {
+    let mut expander = OwningTemplateExpander::new();
+    expander.set_default("");
+    for mount in mounts {
+        for (i, source) in host_sources(&mount.info, host_mounts).iter().enumerate() {
+            let sub = expander
+                .sub("rows")
+                .set("kind", source.kind())
+                .set("host-path", source.path().to_string_lossy());
+            if i == 0 {
+                sub.set("mount-point", mount.info.mount_point.to_string_lossy());
+            }
+        }
+    }
+    let mut tbl = TableBuilder::default();
+    tbl.col(Col::new("Mount Point", "${mount-point}").align_content(Alignment::Left))
+        .col(Col::new("Source", "${kind}").align_content(Alignment::Center))
+        .col(Col::new("Host Path", "${host-path}").align_content(Alignment::Left));
+    writeln!(w, "\nOn the host:\n")?;
+    make_skin(args).write_owning_expander_md(w, &expander, &tbl)
+}
+
+/// Find the main process of a container, by the id appearing in the
+/// cgroups of its processes (docker, podman, containerd), or else by
+/// asking docker then podman, which also accept names
+pub fn container_pid(id: &str) -> Result<u32, String> {
+    let is_id = id.len() >= 6 && id.chars().all(|c| c.is_ascii_hexdigit());
+    if is_id {
+        if let Some(pid) = pid_by_cgroup(id) {
+            return Ok(pid);
+        }
+    }
+    for runtime in ["docker", "podman"] {
+        let output = Command::new(runtime)
+            .args(["inspect", "--format", "{{.State.Pid}}", id])
+            .output();
+        let Ok(output) = output else {
+            continue;
+        };
+        if !output.status.success() {
+            continue;
+        }
+        if let Ok(pid) = String::from_utf8_lossy(&output.stdout).trim().parse::<u32>() {
+            if pid > 0 {
+                return Ok(pid);
+            }
+        }
+    }
+    Err(format!("No running container found for {:?}", id))
+}
+
+/// The lowest pid whose cgroup mentions the container id
+fn pid_by_cgroup(id: &str) -> Option<u32> {
+    fs::read_dir("/proc")
+        .ok()?
+        .flatten()
+        .filter_map(|entry| entry.file_name().to_str()?.parse::<u32>().ok())
+        .filter(|pid| {
+            fs::read_to_string(format!("/proc/{}/cgroup", pid))
+                .is_ok_and(|cgroup| cgroup_has_container(&cgroup, id))
+        })
+        .min()
+}
+
+/// Whether a `/proc/<pid>/cgroup` content designates a container whose id
+/// starts with `id`, eg `0::/system.slice/docker-4f3c...e1.scope`
+fn cgroup_has_container(cgroup: &str, id: &str) -> bool {
+    cgroup.lines().any(|line| {
+        line.rsplit('/')
+            .next()
+            .map(|last| last.trim_end_matches(".scope"))
+            .map(|last| last.rsplit('-').next().unwrap_or(last))
+            .is_some_and(|container| container.len() >= 12 && container.starts_with(id))
+    })
+}
+
+#[test]
+fn test_namespace_mounts() {
+    let content = "\
+1200 1100 0:120 / / rw,relatime master:1 - overlay overlay rw,lowerdir=/var/lib/docker/overlay2/l/AB:/var/lib/docker/overlay2/l/CD,upperdir=/var/lib/docker/overlay2/f00/diff,workdir=/var/lib/docker/overlay2/f00/work
+1201 1200 0:125 / /proc rw,nosuid,nodev,noexec,relatime - proc proc rw
+1210 1200 8:1 /var/lib/docker/volumes/pgdata/_data /var/lib/postgresql/data rw,relatime - ext4 /dev/sda1 rw
+1211 1200 8:1 /var/lib/docker/containers/f00/hosts /etc/hosts rw,relatime - ext4 /dev/sda1 rw
+1212 1200 8:1 /var/lib/docker/volumes/pgdata/_data /backup rw,relatime - ext4 /dev/sda1 rw
+not a mountinfo line
+";
+    let infos = parse_mountinfo(content);
+    assert_eq!(infos.len(), 5);
+    assert!(!infos[3].bound);
+    assert!(infos[4].bound);
+    let overlay = host_sources(&infos[0], &[]);
+    assert_eq!(overlay, vec![
+        HostSource::Upper("/var/lib/docker/overlay2/f00/diff".into()),
+        HostSource::Lower("/var/lib/docker/overlay2/l/AB".into()),
+        HostSource::Lower("/var/lib/docker/overlay2/l/CD".into()),
+    ]);
+    let host = with_host_details("30 1 8:1 / / rw - ext4 /dev/sda1 rw".parse().unwrap(), &[]);
+    assert_eq!(
+        host_sources(&infos[2], &[host]),
+        vec![HostSource::Bind("/var/lib/docker/volumes/pgdata/_data".into())],
+    );
+    assert!(cgroup_has_container(
+        "0::/system.slice/docker-4f3c2a1b9d8e7f6a5b4c3d2e1f0a9b8c7d6e5f4a3b2c1d0e9f8a7b6c5d4e3f2a1.scope\n",
+        "4f3c2a1b9d8e",
+    ));
+    assert!(cgroup_has_container("12:memory:/docker/4f3c2a1b9d8e7f6a\n", "4f3c2a"));
+    assert!(!cgroup_has_container("0::/user.slice/user-1000.slice/session-2.scope\n", "1000"));
+}
@@ -6,6 +6,7 @@ use {
             graph::render_bar_compact,
         },
     },
+    serde_json::{Value, json},
     std::{
         io::{self, Write},
         thread,
@@ -36,6 +37,26 @@ pub fn display_processes<W: Write>(
     // Refresh again to get CPU and I/O deltas
     collector.refresh();
     
+    if args.json {
+        // all processes, so that snapshots can be compared with `provis diff`
+        let processes = collector.get_top_by_cpu(usize::MAX);
+        let value: Vec<Value> = processes
+            .iter()
+            .map(|p| {
+                json!({
+                    "pid": p.pid,
+                    "name": p.name,
+                    "cpu-percent": p.cpu_usage,
+                    "memory": p.mem_usage,
+                    "memory-percent": p.mem_percent,
+                    "disk-read": p.io_read_bytes,
+                    "disk-written": p.io_write_bytes,
+                })
+            })
+            .collect();
+        return writeln!(w, "{}", serde_json::to_string_pretty(&value).unwrap());
+    }
+
     // Get top processes by CPU
     let processes = collector.get_top_by_cpu(10);
     
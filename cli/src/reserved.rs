@@ -0,0 +1,29 @@
+use lfs_core::Stats;
+
+/// Space which the filesystem keeps for root: free, but not
+/// available to the other users (ext4 reserves 5% by default)
+pub fn reserved(stats: &Stats) -> u64 {
+    #[cfg(not(windows))]
+    {
+        stats.bsize * stats.bfree.saturating_sub(stats.bavail)
+    }
+    #[cfg(windows)]
+    {
+        let _ = stats;
+        0
+    }
+}
+
+/// Free space, the reserved blocks included
+pub fn free_for_root(stats: &Stats) -> u64 {
+    stats.available() + reserved(stats)
+}
+
+/// Part of the filesystem reserved for root, between 0 and 1
+pub fn reserved_share(stats: &Stats) -> f64 {
+    if stats.size() == 0 {
+        0.0
+    } else {
+        reserved(stats) as f64 / stats.size() as f64
+    }
+}
@@ -1,6 +1,7 @@
 use {
     crate::{
         col::Col,
+        history::Trends,
         order::Order,
     },
     lfs_core::Mount,
@@ -30,9 +31,13 @@ impl Sorting {
     pub fn sort(
         self,
         mounts: &mut [Mount],
+        trends: &Trends,
     ) {
-        let comparator = self.col.comparator();
-        mounts.sort_by(comparator);
+        if self.col.is_trend() {
+            mounts.sort_by(|a, b| self.col.cmp_trends(a, b, trends));
+        } else {
+            mounts.sort_by(self.col.comparator());
+        }
         if self.order == Order::Desc {
             mounts.reverse();
         }
@@ -0,0 +1,178 @@
+use {
+    lfs_core::DeviceId,
+    std::{
+        fs,
+        path::{Path, PathBuf},
+    },
+};
+
+/// Size of the sectors in which sysfs gives block device sizes,
+/// whatever the real sector size of the device
+static SECTOR_SIZE: u64 = 512;
+
+/// Reader of the block devices described in sysfs.
+///
+/// The root is `/sys` except in tests, which build a fake hierarchy
+#[derive(Debug, Clone)]
+pub struct Sysfs {
+    root: PathBuf,
+}
+
+impl Default for Sysfs {
+    fn default() -> Self {
+        Self::new("/sys")
+    }
+}
+
+impl Sysfs {
+    pub fn new<P: Into<PathBuf>>(root: P) -> Self {
+        Self { root: root.into() }
+    }
+    /// The directory of a block device, partitions included
+    fn device_dir(&self, name: &str) -> PathBuf {
+        self.root.join("class/block").join(name)
+    }
+    /// Read an attribute of a block device, eg `size` or `queue/rotational`
+    pub fn attr(&self, name: &str, attr: &str) -> Option<String> {
+        let value = fs::read_to_string(self.device_dir(name).join(attr)).ok()?;
+        let value = value.trim();
+        (!value.is_empty()).then(|| value.to_string())
+    }
+    fn attr_u64(&self, name: &str, attr: &str) -> Option<u64> {
+        self.attr(name, attr)?.parse().ok()
+    }
+    /// Names of the entries of a directory of a block device, sorted
+    fn list(&self, name: &str, dir: &str) -> Vec<String> {
+        list_names(&self.device_dir(name).join(dir))
+    }
+    /// The top level block devices: disks, but also device-mapper,
+    /// md and loop devices
+    pub fn block_devices(&self) -> Vec<String> {
+        list_names(&self.root.join("block"))
+    }
+    /// The partitions of a disk
+    pub fn partitions(&self, disk: &str) -> Vec<String> {
+        let dir = self.root.join("block").join(disk);
+        list_names(&dir)
+            .into_iter()
+            .filter(|name| dir.join(name).join("partition").exists())
+            .collect()
+    }
+    /// The devices built on this one, eg a LUKS mapping on a partition
+    pub fn holders(&self, name: &str) -> Vec<String> {
+        self.list(name, "holders")
+    }
+    /// The devices this one is built on, eg the members of a RAID array
+    pub fn slaves(&self, name: &str) -> Vec<String> {
+        self.list(name, "slaves")
+    }
+    pub fn is_partition(&self, name: &str) -> bool {
+        self.device_dir(name).join("partition").exists()
+    }
+    /// The disk holding a partition, or the device itself
+    pub fn disk_of(&self, name: &str) -> String {
+        if self.is_partition(name) {
+            let parent = fs::canonicalize(self.device_dir(name))
+                .ok()
+                .and_then(|path| path.parent().and_then(file_name));
+            if let Some(parent) = parent {
+                return parent;
+            }
+        }
+        name.to_string()
+    }
+    /// The physical disk under a device, following the partitions
+    /// and the stacked layers (the first member of an array or volume)
+    pub fn physical_disk(&self, name: &str) -> String {
+        let mut name = self.disk_of(name);
+        for _ in 0..16 {
+            let Some(slave) = self.slaves(&name).into_iter().next() else {
+                break;
+            };
+            name = self.disk_of(&slave);
+        }
+        name
+    }
+    /// The name of the block device with the given id, using `/sys/dev/block`
+    pub fn name_of(&self, dev: DeviceId) -> Option<String> {
+        let link = self
+            .root
+            .join("dev/block")
+            .join(format!("{}:{}", dev.major, dev.minor));
+        file_name(&fs::canonicalize(link).ok()?)
+    }
+    pub fn dev(&self, name: &str) -> Option<DeviceId> {
+        self.attr(name, "dev")?.parse().ok()
+    }
+    /// Size in bytes
+    pub fn size(&self, name: &str) -> Option<u64> {
+        self.attr_u64(name, "size").map(|sectors| sectors * SECTOR_SIZE)
+    }
+    /// Whether the disk is rotational, partitions having the value of their disk
+    pub fn rotational(&self, name: &str) -> Option<bool> {
+        let value = self.attr_u64(&self.disk_of(name), "queue/rotational")?;
+        Some(value != 0)
+    }
+    pub fn model(&self, name: &str) -> Option<String> {
+        self.attr(&self.disk_of(name), "device/model")
+    }
+    /// The bus the disk is attached with, deduced from its place in
+    /// the device hierarchy, `None` for virtual devices
+    pub fn transport(&self, name: &str) -> Option<&'static str> {
+        let disk = self.disk_of(name);
+        let link = fs::read_link(self.root.join("block").join(&disk))
+            .or_else(|_| fs::read_link(self.device_dir(&disk)))
+            .ok()?;
+        transport_of_path(&link.to_string_lossy())
+    }
+}
+
+fn file_name(path: &Path) -> Option<String> {
+    Some(path.file_name()?.to_str()?.to_string())
+}
+
+fn list_names(dir: &Path) -> Vec<String> {
+    let mut names: Vec<String> = fs::read_dir(dir)
+        .map(|entries| {
+            entries
+                .flatten()
+                .filter_map(|e| e.file_name().to_str().map(String::from))
+                .collect()
+        })
+        .unwrap_or_default();
+    names.sort();
+    names
+}
+
+/// Determine the transport from the path of a device in `/sys/devices`,
+/// eg `../devices/pci0000:00/0000:00:17.0/ata1/host0/target0:0:0/0:0:0:0/block/sda`
+fn transport_of_path(path: &str) -> Option<&'static str> {
+    if path.contains("/virtual/") {
+        return None;
+    }
+    let has = |part: &str| path.split('/').any(|c| c.starts_with(part));
+    if has("nvme") {
+        Some("nvme")
+    } else if has("usb") {
+        Some("usb")
+    } else if has("virtio") {
+        Some("virtio")
+    } else if has("ata") {
+        Some("sata")
+    } else if has("mmc") {
+        Some("mmc")
+    } else if has("host") {
+        Some("scsi")
+    } else {
+        None
+    }
+}
+
+#[test]
+fn test_transport_of_path() {
+    assert_eq!(transport_of_path("../devices/pci0000:00/0000:00:17.0/ata1/host0/target0:0:0/0:0:0:0/block/sda"), Some("sata"));
+    assert_eq!(transport_of_path("../devices/pci0000:00/0000:00:1d.0/0000:3d:00.0/nvme/nvme0/nvme0n1"), Some("nvme"));
+    assert_eq!(transport_of_path("../devices/pci0000:00/0000:00:14.0/usb2/2-1/2-1:1.0/host4/target4:0:0/4:0:0:0/block/sdb"), Some("usb"));
+    assert_eq!(transport_of_path("../devices/pci0000:00/0000:00:02.0/virtio1/block/vda"), Some("virtio"));
+    assert_eq!(transport_of_path("../devices/virtual/block/dm-0"), None);
+}
@@ -1,7 +1,13 @@
 use {
     crate::{
         Args,
+        age::Age,
         col::Col,
+        diskio,
+        hardware,
+        group::{Group, Totals},
+        history::Trends,
+        reserved,
     },
     lfs_core::*,
     std::io::Write,
@@ -12,6 +18,7 @@ use {
         crossterm::style::Color::*,
         minimad::{
             self,
+            OwningSubTemplateExpander,
             OwningTemplateExpander,
             TableBuilder,
         },
@@ -30,68 +37,171 @@ pub fn write<W: Write>(
     mounts: &[&Mount],
     color: bool,
     args: &Args,
+    trends: &Trends,
 ) -> std::io::Result<()> {
     if args.cols.is_empty() {
         return Ok(());
     }
-    let units = args.units;
     let mut expander = OwningTemplateExpander::new();
     expander.set_default("");
     for mount in mounts {
-        let sub = expander
-            .sub("rows")
-            .set(
-                "id",
-                mount
-                    .info
-                    .id
-                    .as_ref()
-                    .map_or("".to_string(), |i| i.to_string()),
-            )
-            .set("dev", mount.info.dev)
-            .set("filesystem", &mount.info.fs)
-            .set("disk", mount.disk.as_ref().map_or("", |d| d.disk_type()))
-            .set("type", &mount.info.fs_type)
-            .set("mount-point", mount.info.mount_point.to_string_lossy())
-            .set("mount-options", mount.info.options_string())
-            .set_option("uuid", mount.uuid.as_ref())
-            .set_option("part_uuid", mount.part_uuid.as_ref())
-            .set_option(
-                "compress-level",
-                mount.info.option_value("compress"),
-            );
-        if let Some(label) = &mount.fs_label {
-            sub.set("label", label);
+        set_mount_row(expander.sub("rows"), mount, args, trends);
+    }
+    write_expander(w, &expander, color, args)
+}
+
+/// Write the mounts group by group, each one followed by a row with its
+/// subtotals, then a row with the totals of all the mounts
+pub fn write_groups<W: Write>(
+    w: &mut W,
+    groups: &[Group],
+    color: bool,
+    args: &Args,
+    trends: &Trends,
+) -> std::io::Result<()> {
+    if args.cols.is_empty() {
+        return Ok(());
+    }
+    let mut expander = OwningTemplateExpander::new();
+    expander.set_default("");
+    for group in groups {
+        for mount in &group.mounts {
+            set_mount_row(expander.sub("rows"), mount, args, trends);
         }
-        if mount.is_remote() {
-            sub.set("remote", "x");
+        if group.totals.filesystems > 0 {
+            set_totals_row(expander.sub("rows"), &format!("{} total", group.key), &group.totals, args);
         }
-        if let Some(stats) = mount.stats() {
-            let use_share = stats.use_share();
-            let free_share = 1.0 - use_share;
-            sub.set("size", units.fmt(stats.size()))
-                .set("used", units.fmt(stats.used()))
-                .set("use-percents", format!("{:.0}%", 100.0 * use_share))
-                .set_md("bar", progress_bar_md(use_share, BAR_WIDTH, args.ascii))
-                .set("free", units.fmt(stats.available()))
-                .set("free-percents", format!("{:.0}%", 100.0 * free_share));
-            if let Some(inodes) = &stats.inodes {
-                let iuse_share = inodes.use_share();
-                sub.set("inodes", inodes.files)
-                    .set("iused", inodes.used())
-                    .set("iuse-percents", format!("{:.0}%", 100.0 * iuse_share))
-                    .set_md(
-                        "ibar",
-                        progress_bar_md(iuse_share, INODES_BAR_WIDTH, args.ascii),
-                    )
-                    .set("ifree", inodes.favail);
-            }
-        } else if mount.is_timeout() {
-            sub.set("use-error", "timeout");
-        } else if mount.is_unreachable() {
-            sub.set("use-error", "unreachable");
+    }
+    let mounts: Vec<&Mount> = groups.iter().flat_map(|g| g.mounts.iter().copied()).collect();
+    set_totals_row(expander.sub("rows"), "total", &Totals::of(&mounts), args);
+    write_expander(w, &expander, color, args)
+}
+
+fn set_mount_row(
+    sub: &mut OwningSubTemplateExpander,
+    mount: &Mount,
+    args: &Args,
+    trends: &Trends,
+) {
+    let units = args.units;
+    sub.set(
+            "id",
+            mount
+                .info
+                .id
+                .as_ref()
+                .map_or("".to_string(), |i| i.to_string()),
+        )
+        .set("dev", mount.info.dev)
+        .set("filesystem", &mount.info.fs)
+        .set("disk", mount.disk.as_ref().map_or("", |d| d.disk_type()))
+        .set("type", &mount.info.fs_type)
+        .set("mount-point", mount.info.mount_point.to_string_lossy())
+        .set("mount-options", mount.info.options_string())
+        .set_option("uuid", mount.uuid.as_ref())
+        .set_option("part_uuid", mount.part_uuid.as_ref())
+        .set_option(
+            "compress-level",
+            mount.info.option_value("compress"),
+        );
+    if let Some(label) = &mount.fs_label {
+        sub.set("label", label);
+    }
+    let io = if args.cols.cols().iter().any(|c| c.is_io()) {
+        diskio::of_mount(mount)
+    } else {
+        None
+    };
+    if let Some(io) = io {
+        sub.set("read-rate", format!("{}/s", units.fmt(io.read_rate as u64)))
+            .set("write-rate", format!("{}/s", units.fmt(io.write_rate as u64)))
+            .set("iops", format!("{:.0}", io.iops))
+            .set_option("latency", io.latency.map(|l| format!("{:.1}ms", l)))
+            .set("util", format!("{:.0}%", io.util));
+    }
+    let trend = if args.cols.cols().iter().any(|c| c.is_trend()) {
+        trends.of_mount(mount)
+    } else {
+        None
+    };
+    if let Some(trend) = trend {
+        sub.set("growth", units.fmt_signed(trend.growth_per_day as i64))
+            .set_option("eta-full", trend.eta_full.map(|eta| Age(eta).precise()));
+    }
+    if let Some(hardware) = hardware::of_mount(mount) {
+        sub.set_option("model", hardware.vendor_model())
+            .set_option("serial", hardware.serial.as_ref())
+            .set_option("transport", hardware.transport)
+            .set_option("scheduler", hardware.scheduler.as_ref())
+            .set_option("logical-sector", hardware.logical_sector_size)
+            .set_option("physical-sector", hardware.physical_sector_size)
+            .set_option("discard", hardware.discard_str())
+            .set_option("read-ahead", hardware.read_ahead.map(|r| units.fmt(r)));
+    }
+    if mount.is_remote() {
+        sub.set("remote", "x");
+    }
+    if let Some(stats) = mount.stats() {
+        let use_share = stats.use_share();
+        let free_share = 1.0 - use_share;
+        sub.set("size", units.fmt(stats.size()))
+            .set("used", units.fmt(stats.used()))
+            .set("use-percents", format!("{:.0}%", 100.0 * use_share))
+            .set_md("bar", progress_bar_md(use_share, BAR_WIDTH, args.ascii))
+            .set("free", units.fmt(stats.available()))
+            .set("free-percents", format!("{:.0}%", 100.0 * free_share))
+            .set("reserved", units.fmt(reserved::reserved(stats)))
+            .set("reserved-percents", format!("{:.1}%", 100.0 * reserved::reserved_share(stats)))
+            .set("free-root", units.fmt(reserved::free_for_root(stats)));
+        if let Some(inodes) = &stats.inodes {
+            let iuse_share = inodes.use_share();
+            sub.set("inodes", inodes.files)
+                .set("iused", inodes.used())
+                .set("iuse-percents", format!("{:.0}%", 100.0 * iuse_share))
+                .set_md(
+                    "ibar",
+                    progress_bar_md(iuse_share, INODES_BAR_WIDTH, args.ascii),
+                )
+                .set("ifree", inodes.favail);
         }
+    } else if mount.is_timeout() {
+        sub.set("use-error", "timeout");
+    } else if mount.is_unreachable() {
+        sub.set("use-error", "unreachable");
     }
+}
+
+fn set_totals_row(
+    sub: &mut OwningSubTemplateExpander,
+    label: &str,
+    totals: &Totals,
+    args: &Args,
+) {
+    let units = args.units;
+    let use_share = totals.use_share();
+    sub.set("total", format!("{} ", label))
+        .set("size", units.fmt(totals.size))
+        .set("used", units.fmt(totals.used))
+        .set("use-percents", format!("{:.0}%", 100.0 * use_share))
+        .set_md("bar", progress_bar_md(use_share, BAR_WIDTH, args.ascii))
+        .set("free", units.fmt(totals.available))
+        .set("free-percents", format!("{:.0}%", 100.0 * (1.0 - use_share)));
+    if totals.has_inodes {
+        let iuse_share = totals.inodes_use_share();
+        sub.set("inodes", totals.inodes)
+            .set("iused", totals.iused)
+            .set("iuse-percents", format!("{:.0}%", 100.0 * iuse_share))
+            .set_md("ibar", progress_bar_md(iuse_share, INODES_BAR_WIDTH, args.ascii))
+            .set("ifree", totals.ifree);
+    }
+}
+
+fn write_expander<W: Write>(
+    w: &mut W,
+    expander: &OwningTemplateExpander,
+    color: bool,
+    args: &Args,
+) -> std::io::Result<()> {
     let mut skin = if color {
         make_colored_skin()
     } else {
@@ -102,42 +212,64 @@ pub fn write<W: Write>(
     }
 
     let mut tbl = TableBuilder::default();
-    for col in args.cols.cols() {
+    for (i, col) in args.cols.cols().iter().enumerate() {
+        let template = match col {
+            Col::Id => "${id}",
+            Col::Dev => "${dev}",
+            Col::Filesystem => "${filesystem}",
+            Col::Label => "${label}",
+            Col::Disk => "${disk}",
+            Col::Type => "${type}",
+            Col::Remote => "${remote}",
+            Col::Used => "~~${used}~~",
+            Col::Use => "~~${use-percents}~~ ${bar}~~${use-error}~~",
+            Col::UsePercent => "~~${use-percents}~~",
+            Col::Free => "${free}",
+            Col::FreePercent => "${free-percents}",
+            Col::Reserved => "${reserved}",
+            Col::ReservedPercent => "${reserved-percents}",
+            Col::FreeRoot => "${free-root}",
+            Col::Size => "**${size}**",
+            Col::InodesFree => "${ifree}",
+            Col::InodesUsed => "~~${iused}~~",
+            Col::InodesUse => "~~${iuse-percents}~~ ${ibar}",
+            Col::InodesUsePercent => "~~${iuse-percents}~~",
+            Col::InodesCount => "**${inodes}**",
+            Col::MountPoint => "${mount-point}",
+            Col::Uuid => "${uuid}",
+            Col::PartUuid => "${part_uuid}",
+            Col::MountOptions => "${mount-options}",
+            Col::CompressLevel => "${compress-level}",
+            Col::Model => "${model}",
+            Col::Serial => "${serial}",
+            Col::Transport => "${transport}",
+            Col::Scheduler => "${scheduler}",
+            Col::LogicalSector => "${logical-sector}",
+            Col::PhysicalSector => "${physical-sector}",
+            Col::Discard => "${discard}",
+            Col::ReadAhead => "${read-ahead}",
+            Col::ReadRate => "${read-rate}",
+            Col::WriteRate => "${write-rate}",
+            Col::Iops => "${iops}",
+            Col::Latency => "${latency}",
+            Col::Util => "${util}",
+            Col::EtaFull => "${eta-full}",
+            Col::Growth => "${growth}",
+        };
+        // the label of the total rows goes in the first column
+        let template = if i == 0 {
+            format!("${{total}}{}", template)
+        } else {
+            template.to_string()
+        };
         tbl.col(
-            minimad::Col::new(
-                col.title(),
-                match col {
-                    Col::Id => "${id}",
-                    Col::Dev => "${dev}",
-                    Col::Filesystem => "${filesystem}",
-                    Col::Label => "${label}",
-                    Col::Disk => "${disk}",
-                    Col::Type => "${type}",
-                    Col::Remote => "${remote}",
-                    Col::Used => "~~${used}~~",
-                    Col::Use => "~~${use-percents}~~ ${bar}~~${use-error}~~",
-                    Col::UsePercent => "~~${use-percents}~~",
-                    Col::Free => "${free}",
-                    Col::FreePercent => "${free-percents}",
-                    Col::Size => "**${size}**",
-                    Col::InodesFree => "${ifree}",
-                    Col::InodesUsed => "~~${iused}~~",
-                    Col::InodesUse => "~~${iuse-percents}~~ ${ibar}",
-                    Col::InodesUsePercent => "~~${iuse-percents}~~",
-                    Col::InodesCount => "**${inodes}**",
-                    Col::MountPoint => "${mount-point}",
-                    Col::Uuid => "${uuid}",
-                    Col::PartUuid => "${part_uuid}",
-                    Col::MountOptions => "${mount-options}",
-                    Col::CompressLevel => "${compress-level}",
-                },
-            )
-            .align_content(col.content_align())
-            .align_header(col.header_align()),
+            minimad::Col::new(col.title(), template)
+                .align_content(col.content_align())
+                .align_header(col.header_align()),
         );
     }
 
-    skin.write_owning_expander_md(w, &expander, &tbl)
+    skin.write_owning_expander_md(w, expander, &tbl)
 }
 
 fn make_colored_skin() -> MadSkin {
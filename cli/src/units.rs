@@ -31,6 +31,14 @@ impl FromStr for Units {
 static PREFIXES: &[char] = &['K', 'M', 'G', 'T', 'P'];
 
 impl Units {
+    /// Format a change of size, always with its sign, eg `+1.2G` or `-300M`
+    pub fn fmt_signed(
+        self,
+        change: i64,
+    ) -> String {
+        let sign = if change < 0 { '-' } else { '+' };
+        format!("{}{}", sign, self.fmt(change.unsigned_abs()))
+    }
     pub fn fmt(
         self,
         size: u64,
@@ -0,0 +1,103 @@
+use {
+    super::run,
+    std::{
+        collections::{HashMap, HashSet},
+        path::Path,
+    },
+};
+
+/// A btrfs subvolume, or snapshot
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Subvolume {
+    pub id: u64,
+    /// path relative to the top level subvolume
+    pub path: String,
+    pub snapshot: bool,
+    /// bytes referenced by the subvolume, only known with qgroups
+    pub referenced: Option<u64>,
+    /// bytes only referenced by this subvolume, which deleting it
+    /// would free, only known with qgroups
+    pub exclusive: Option<u64>,
+}
+
+/// Parse the output of `btrfs subvolume list`, eg
+/// `ID 256 gen 10 top level 5 path home`, into ids and paths
+pub fn parse_subvolume_list(output: &str) -> Vec<(u64, String)> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let id = line.strip_prefix("ID ")?.split_whitespace().next()?.parse().ok()?;
+            let (_, path) = line.split_once(" path ")?;
+            Some((id, path.to_string()))
+        })
+        .collect()
+}
+
+/// Parse the output of `btrfs qgroup show --raw` into the referenced
+/// and exclusive sizes of the level 0 qgroups, which are the ones of
+/// the subvolumes, by subvolume id
+pub fn parse_qgroups(output: &str) -> HashMap<u64, (u64, u64)> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split_whitespace();
+            let id = fields.next()?.strip_prefix("0/")?.parse().ok()?;
+            let referenced = fields.next()?.parse().ok()?;
+            let exclusive = fields.next()?.parse().ok()?;
+            Some((id, (referenced, exclusive)))
+        })
+        .collect()
+}
+
+/// Read the subvolumes of the btrfs filesystem mounted at `mount_point`.
+///
+/// Sizes are missing when quotas aren't enabled on the filesystem
+pub fn read_subvolumes(mount_point: &Path) -> Result<Vec<Subvolume>, String> {
+    let mp = mount_point.to_string_lossy();
+    let list = run("btrfs", &["subvolume", "list", &mp])?;
+    let snapshots: HashSet<u64> = parse_subvolume_list(&run("btrfs", &["subvolume", "list", "-s", &mp])?)
+        .into_iter()
+        .map(|(id, _)| id)
+        .collect();
+    let qgroups = run("btrfs", &["qgroup", "show", "--raw", &mp])
+        .map(|output| parse_qgroups(&output))
+        .unwrap_or_default();
+    Ok(parse_subvolume_list(&list)
+        .into_iter()
+        .map(|(id, path)| {
+            let sizes = qgroups.get(&id);
+            Subvolume {
+                id,
+                path,
+                snapshot: snapshots.contains(&id),
+                referenced: sizes.map(|s| s.0),
+                exclusive: sizes.map(|s| s.1),
+            }
+        })
+        .collect())
+}
+
+#[test]
+fn test_parse_btrfs() {
+    let list = "\
+ID 256 gen 2034 top level 5 path @
+ID 257 gen 2030 top level 5 path @home
+ID 260 gen 1990 top level 256 path @/var/lib/my docker
+ID 270 gen 1500 top level 5 path .snapshots/1/snapshot
+";
+    let subvolumes = parse_subvolume_list(list);
+    assert_eq!(subvolumes.len(), 4);
+    assert_eq!(subvolumes[2], (260, "@/var/lib/my docker".to_string()));
+    // btrfs-progs 6 adds a path column, older versions don't
+    let qgroups = "\
+Qgroupid    Referenced    Exclusive   Path
+--------    ----------    ---------   ----
+0/5              16384        16384   <toplevel>
+0/256       8589934592    104857600   @
+0/270       8489934592      4194304   .snapshots/1/snapshot
+1/100       8589934592   8589934592   <0 member qgroups>
+";
+    let qgroups = parse_qgroups(qgroups);
+    assert_eq!(qgroups.len(), 3);
+    assert_eq!(qgroups[&270], (8_489_934_592, 4_194_304));
+}
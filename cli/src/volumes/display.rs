@@ -0,0 +1,235 @@
+use {
+    super::{
+        btrfs::{self, Subvolume},
+        zfs::{self, Dataset},
+    },
+    crate::{
+        Args,
+        directory::display::make_skin,
+    },
+    lfs_core::{DeviceId, Mount, ReadOptions},
+    serde_json::{Value, json},
+    std::io::{self, Write},
+    termimad::minimad::{OwningTemplateExpander, TableBuilder, Col, Alignment},
+};
+
+/// A filesystem whose subvolumes or datasets are listed
+enum Volumes<'m> {
+    Btrfs {
+        mount: &'m Mount,
+        subvolumes: Result<Vec<Subvolume>, String>,
+    },
+    Zfs {
+        pool: String,
+        datasets: Result<Vec<Dataset>, String>,
+    },
+}
+
+/// The btrfs filesystems, each one read from its mount closest to the
+/// root, then the ZFS pools
+fn read_volumes(mounts: &[Mount]) -> Vec<Volumes<'_>> {
+    let mut btrfs_mounts: Vec<&Mount> = Vec::new();
+    let mut pools: Vec<String> = Vec::new();
+    for mount in mounts {
+        match mount.info.fs_type.as_str() {
+            "btrfs" => {
+                match btrfs_mounts.iter_mut().find(|m| m.info.dev == mount.info.dev) {
+                    Some(m) => {
+                        let depth = |m: &Mount| m.info.mount_point.components().count();
+                        if depth(mount) < depth(m) {
+                            *m = mount;
+                        }
+                    }
+                    None => btrfs_mounts.push(mount),
+                }
+            }
+            "zfs" => {
+                let pool = zfs::pool_of(&mount.info.fs);
+                if !pools.iter().any(|p| p == pool) {
+                    pools.push(pool.to_string());
+                }
+            }
+            _ => {}
+        }
+    }
+    let btrfs = btrfs_mounts.into_iter().map(|mount| Volumes::Btrfs {
+        mount,
+        subvolumes: btrfs::read_subvolumes(&mount.info.mount_point),
+    });
+    let zfs = pools.into_iter().map(|pool| Volumes::Zfs {
+        datasets: zfs::read_datasets(&pool),
+        pool,
+    });
+    btrfs.chain(zfs).collect()
+}
+
+/// Display the subvolumes of the btrfs filesystems and the
+/// datasets of the ZFS pools
+pub fn display_volumes<W: Write>(
+    w: &mut W,
+    args: &Args,
+) -> io::Result<()> {
+    let mut options = ReadOptions::default();
+    if let Some(timeout) = args.timeout {
+        options = options.stats_timeout(timeout.as_duration());
+    }
+    let mut mounts = match lfs_core::read_mounts(&options) {
+        Ok(mounts) => mounts,
+        Err(e) => {
+            eprintln!("Error reading mounts: {}", e);
+            return Ok(());
+        }
+    };
+    if let Some(path) = &args.path {
+        let dev = match DeviceId::of_path(path) {
+            Ok(dev) => dev,
+            Err(e) => {
+                eprintln!("Error getting device of path {}: {}", path.display(), e);
+                return Ok(());
+            }
+        };
+        mounts.retain(|m| m.info.dev == dev);
+    }
+    let volumes = read_volumes(&mounts);
+    if args.json {
+        let value: Vec<Value> = volumes.iter().map(volumes_to_json).collect();
+        return writeln!(w, "{}", serde_json::to_string_pretty(&value).unwrap());
+    }
+    if volumes.is_empty() {
+        return writeln!(w, "no btrfs or zfs filesystem found");
+    }
+    for volumes in &volumes {
+        match volumes {
+            Volumes::Btrfs { mount, subvolumes } => {
+                writeln!(
+                    w,
+                    "\nbtrfs {} on {}:\n",
+                    mount.info.mount_point.display(),
+                    mount.info.fs,
+                )?;
+                match subvolumes {
+                    Ok(subvolumes) => write_subvolumes(w, subvolumes, args)?,
+                    Err(e) => writeln!(w, "{}", e)?,
+                }
+            }
+            Volumes::Zfs { pool, datasets } => {
+                writeln!(w, "\nzfs pool {}:\n", pool)?;
+                match datasets {
+                    Ok(datasets) => write_datasets(w, datasets, args)?,
+                    Err(e) => writeln!(w, "{}", e)?,
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+fn volumes_to_json(volumes: &Volumes) -> Value {
+    match volumes {
+        Volumes::Btrfs { mount, subvolumes } => {
+            let subvolumes: Result<Vec<Value>, &String> = subvolumes.as_ref().map(|subvolumes| {
+                subvolumes
+                    .iter()
+                    .map(|s| {
+                        json!({
+                            "id": s.id,
+                            "path": s.path,
+                            "snapshot": s.snapshot,
+                            "referenced": s.referenced,
+                            "exclusive": s.exclusive,
+                        })
+                    })
+                    .collect()
+            });
+            json!({
+                "type": "btrfs",
+                "fs": mount.info.fs,
+                "mount-point": mount.info.mount_point,
+                "subvolumes": subvolumes.as_ref().ok(),
+                "error": subvolumes.err(),
+            })
+        }
+        Volumes::Zfs { pool, datasets } => {
+            let datasets: Result<Vec<Value>, &String> = datasets.as_ref().map(|datasets| {
+                datasets
+                    .iter()
+                    .map(|d| {
+                        json!({
+                            "name": d.name,
+                            "used": d.used,
+                            "referenced": d.referenced,
+                            "compress-ratio": d.compress_ratio,
+                            "used-by-snapshots": d.used_by_snapshots,
+                            "mount-point": d.mount_point,
+                        })
+                    })
+                    .collect()
+            });
+            json!({
+                "type": "zfs",
+                "pool": pool,
+                "datasets": datasets.as_ref().ok(),
+                "error": datasets.err(),
+            })
+        }
+    }
+}
+
+fn write_subvolumes<W: Write>(
+    w: &mut W,
+    subvolumes: &[Subvolume],
+    args: &Args,
+) -> io::Result<()> {
+    if subvolumes.is_empty() {
+        return writeln!(w, "no subvolume");
+    }
+    let mut expander = OwningTemplateExpander::new();
+    expander.set_default("");
+    for subvolume in subvolumes {
+        expander
+            .sub("rows")
+            .set("id", subvolume.id)
+            .set("path", &subvolume.path)
+            .set("type", if subvolume.snapshot { "snapshot" } else { "subvol" })
+            .set_option("referenced", subvolume.referenced.map(|s| args.units.fmt(s)))
+            .set_option("exclusive", subvolume.exclusive.map(|s| args.units.fmt(s)));
+    }
+    let mut tbl = TableBuilder::default();
+    tbl.col(Col::new("ID", "${id}").align_content(Alignment::Right))
+        .col(Col::new("Path", "${path}").align_content(Alignment::Left))
+        .col(Col::new("Type", "${type}").align_content(Alignment::Center))
+        .col(Col::new("Referenced", "${referenced}").align_content(Alignment::Right))
+        .col(Col::new("Exclusive", "${exclusive}").align_content(Alignment::Right));
+    make_skin(args).write_owning_expander_md(w, &expander, &tbl)?;
+    if subvolumes.iter().all(|s| s.referenced.is_none()) {
+        writeln!(w, "Sizes need quotas: `btrfs quota enable <mount point>`")?;
+    }
+    Ok(())
+}
+
+fn write_datasets<W: Write>(
+    w: &mut W,
+    datasets: &[Dataset],
+    args: &Args,
+) -> io::Result<()> {
+    let mut expander = OwningTemplateExpander::new();
+    expander.set_default("");
+    for dataset in datasets {
+        expander
+            .sub("rows")
+            .set("name", &dataset.name)
+            .set("used", args.units.fmt(dataset.used))
+            .set("referenced", args.units.fmt(dataset.referenced))
+            .set_option("ratio", dataset.compress_ratio.map(|r| format!("{:.2}x", r)))
+            .set("snapshots", args.units.fmt(dataset.used_by_snapshots))
+            .set_option("mount-point", dataset.mount_point.as_ref());
+    }
+    let mut tbl = TableBuilder::default();
+    tbl.col(Col::new("Dataset", "${name}").align_content(Alignment::Left))
+        .col(Col::new("Used", "${used}").align_content(Alignment::Right))
+        .col(Col::new("Referenced", "${referenced}").align_content(Alignment::Right))
+        .col(Col::new("Ratio", "${ratio}").align_content(Alignment::Right))
+        .col(Col::new("Snapshots", "${snapshots}").align_content(Alignment::Right))
+        .col(Col::new("Mount Point", "${mount-point}").align_content(Alignment::Left));
+    make_skin(args).write_owning_expander_md(w, &expander, &tbl)
+}
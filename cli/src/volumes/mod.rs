@@ -0,0 +1,31 @@
+//! Subvolumes and datasets
+//!
+//! Btrfs and ZFS filesystems hold several subvolumes or datasets sharing
+//! the space of the device or pool, which the disk view only shows as
+//! single mounts. This module lists them, with their sizes, by parsing
+//! the output of the `btrfs` and `zfs` commands.
+
+pub mod btrfs;
+pub mod display;
+pub mod zfs;
+
+pub use display::display_volumes;
+
+use std::process::Command;
+
+/// Run a command and return its standard output, or a message
+/// telling why it failed
+fn run(
+    program: &str,
+    args: &[&str],
+) -> Result<String, String> {
+    let output = Command::new(program)
+        .args(args)
+        .output()
+        .map_err(|e| format!("can't run {}: {}", program, e))?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(format!("{} {} failed: {}", program, args.join(" "), stderr.trim()));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
@@ -0,0 +1,76 @@
+use super::run;
+
+/// A ZFS filesystem or volume
+#[derive(Debug, Clone, PartialEq)]
+pub struct Dataset {
+    /// full name, eg `rpool/home/alice`
+    pub name: String,
+    /// bytes used by the dataset and its descendants, snapshots included
+    pub used: u64,
+    /// bytes accessible from the dataset
+    pub referenced: u64,
+    pub compress_ratio: Option<f64>,
+    /// bytes which would be freed by deleting all snapshots of the dataset
+    pub used_by_snapshots: u64,
+    /// `None` for volumes and unmountable datasets
+    pub mount_point: Option<String>,
+}
+
+static PROPERTIES: &str = "name,used,referenced,compressratio,usedbysnapshots,mountpoint";
+
+/// Parse the output of `zfs list -Hp -o name,used,referenced,compressratio,usedbysnapshots,mountpoint`
+pub fn parse_list(output: &str) -> Vec<Dataset> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split('\t').collect();
+            let [name, used, referenced, ratio, snapshots, mount_point] = fields[..] else {
+                return None;
+            };
+            Some(Dataset {
+                name: name.to_string(),
+                used: used.parse().ok()?,
+                referenced: referenced.parse().ok()?,
+                // parsable output is `1.50` in recent versions, `1.50x` in older ones
+                compress_ratio: ratio.trim_end_matches('x').parse().ok(),
+                used_by_snapshots: snapshots.parse().unwrap_or(0),
+                mount_point: match mount_point {
+                    "-" | "none" | "legacy" => None,
+                    mp => Some(mp.to_string()),
+                },
+            })
+        })
+        .collect()
+}
+
+/// The pool of a dataset, eg `rpool` for `rpool/home`
+pub fn pool_of(dataset: &str) -> &str {
+    dataset.split('/').next().unwrap_or(dataset)
+}
+
+/// Read the filesystems and volumes of a pool
+pub fn read_datasets(pool: &str) -> Result<Vec<Dataset>, String> {
+    let output = run(
+        "zfs",
+        &["list", "-Hp", "-r", "-t", "filesystem,volume", "-o", PROPERTIES, pool],
+    )?;
+    Ok(parse_list(&output))
+}
+
+#[test]
+fn test_parse_zfs() {
+    let output = "\
+rpool\t48318382080\t98304\t1.52\t0\t/
+rpool/ROOT\t21474836480\t98304\t1.61x\t0\tnone
+rpool/home\t26843545600\t25769803776\t1.30\t1073741824\t/home
+rpool/swap\t4294967296\t12288\t1.00\t0\t-
+broken line
+";
+    let datasets = parse_list(output);
+    assert_eq!(datasets.len(), 4);
+    assert_eq!(datasets[1].compress_ratio, Some(1.61));
+    assert_eq!(datasets[1].mount_point, None);
+    assert_eq!(datasets[2].used_by_snapshots, 1_073_741_824);
+    assert_eq!(datasets[2].mount_point.as_deref(), Some("/home"));
+    assert_eq!(pool_of("rpool/home/alice"), "rpool");
+}
@@ -1,6 +1,14 @@
 #[allow(clippy::match_like_matches_macro)]
 fn main() {
-    if provis_cli::run().is_err() {
-        std::process::exit(141);
+    match provis_cli::run() {
+        Ok(()) => {}
+        // the output was closed, eg piped into `head`
+        Err(e) if e.kind() == std::io::ErrorKind::BrokenPipe => {
+            std::process::exit(141);
+        }
+        Err(e) => {
+            eprintln!("provis: {}", e);
+            std::process::exit(1);
+        }
     }
 }